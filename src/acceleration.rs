@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("acceleration rate must be greater than 0 and no more than 1.0")]
+struct RateRangeError;
+
+#[derive(Error, Debug)]
+#[error("max multiplier must be no less than 1.0 and no more than 20.0")]
+struct MaxMultiplierRangeError;
+
+#[derive(Error, Debug)]
+#[error("reset after must be greater than 0")]
+struct ResetAfterRangeError;
+
+/// How the step size grows with each repeated press of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccelerationCurve {
+    /// Grows the step size by the rate with each repeat.
+    #[default]
+    Linear,
+    /// Grows the step size by the rate compounded with each repeat.
+    Exponential,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    repeats: u32,
+    last: Instant,
+}
+
+/// Grows the step of a key while it is held down or tapped in quick succession.
+///
+/// The step starts over when another key is pressed or the presses pause for long enough.  Global
+/// hotkeys don't report releases, so releasing the key only resets the step once the pause has
+/// elapsed.
+#[derive(Debug)]
+pub struct Accelerator<K> {
+    curve: AccelerationCurve,
+    rate: f32,
+    max_multiplier: f32,
+    reset_after: Duration,
+    presses: HashMap<K, Press>,
+}
+
+impl<K> Default for Accelerator<K> {
+    fn default() -> Self {
+        Self {
+            curve: AccelerationCurve::default(),
+            rate: 0.15,
+            max_multiplier: 5.0,
+            reset_after: Duration::from_millis(600),
+            presses: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Copy + Eq + Hash> Accelerator<K> {
+    pub const fn set_curve(&mut self, curve: AccelerationCurve) {
+        self.curve = curve;
+    }
+
+    /// Sets how quickly the multiplier grows while the key is held.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rate isn't above 0 and no more than 1.
+    pub fn set_rate(&mut self, rate: f32) -> Result<()> {
+        if rate <= 0.0 || rate > 1.0 {
+            return Err(RateRangeError.into());
+        }
+        self.rate = rate;
+        Ok(())
+    }
+
+    /// Sets the largest multiplier that a held key reaches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the multiplier is less than 1 or more than 20.
+    pub fn set_max_multiplier(&mut self, max_multiplier: f32) -> Result<()> {
+        if !(1.0..=20.0).contains(&max_multiplier) {
+            return Err(MaxMultiplierRangeError.into());
+        }
+        self.max_multiplier = max_multiplier;
+        Ok(())
+    }
+
+    /// Sets how long the key must be released for before the multiplier starts over.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the duration is 0.
+    pub fn set_reset_after(&mut self, reset_after: Duration) -> Result<()> {
+        if reset_after.is_zero() {
+            return Err(ResetAfterRangeError.into());
+        }
+        self.reset_after = reset_after;
+        Ok(())
+    }
+
+    /// Records a press of the key, returning the multiplier to apply to its step.
+    pub fn press(&mut self, key: K, now: Instant) -> f32 {
+        self.presses.retain(|pressed_key, _| *pressed_key == key);
+        let reset_after = self.reset_after;
+        let repeats = self
+            .presses
+            .entry(key)
+            .and_modify(|press| {
+                if now.duration_since(press.last) < reset_after {
+                    press.repeats += 1;
+                } else {
+                    press.repeats = 0;
+                }
+                press.last = now;
+            })
+            .or_insert(Press {
+                repeats: 0,
+                last: now,
+            })
+            .repeats;
+        self.multiplier(repeats)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn multiplier(&self, repeats: u32) -> f32 {
+        let multiplier = match self.curve {
+            AccelerationCurve::Linear => self.rate.mul_add(repeats as f32, 1.0),
+            AccelerationCurve::Exponential => (1.0 + self.rate).powf(repeats as f32),
+        };
+        multiplier.min(self.max_multiplier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn press_repeatedly(accelerator: &mut Accelerator<u8>, start: Instant, count: u32) -> f32 {
+        (0..count)
+            .map(|index| accelerator.press(1, start + Duration::from_millis(30) * index))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn linear() {
+        let mut accelerator = Accelerator::default();
+        let start = Instant::now();
+        assert_close(press_repeatedly(&mut accelerator, start, 1), 1.0);
+        let start = start + Duration::from_secs(10);
+        assert_close(press_repeatedly(&mut accelerator, start, 5), 1.6);
+        let start = start + Duration::from_secs(10);
+        assert_close(press_repeatedly(&mut accelerator, start, 100), 5.0);
+    }
+
+    #[test]
+    fn exponential() {
+        let mut accelerator = Accelerator::default();
+        accelerator.set_curve(AccelerationCurve::Exponential);
+        accelerator.set_rate(0.5).unwrap();
+        accelerator.set_max_multiplier(10.0).unwrap();
+        let start = Instant::now();
+        assert_close(press_repeatedly(&mut accelerator, start, 3), 2.25);
+        let start = start + Duration::from_secs(10);
+        assert_close(press_repeatedly(&mut accelerator, start, 20), 10.0);
+    }
+
+    #[test]
+    fn resets_after_pause() {
+        let mut accelerator = Accelerator::default();
+        let start = Instant::now();
+        assert!(press_repeatedly(&mut accelerator, start, 10) > 1.0);
+        assert_close(accelerator.press(1, start + Duration::from_secs(2)), 1.0);
+    }
+
+    #[test]
+    fn resets_on_other_key() {
+        let mut accelerator = Accelerator::default();
+        let start = Instant::now();
+        assert!(press_repeatedly(&mut accelerator, start, 10) > 1.0);
+        assert_close(
+            accelerator.press(2, start + Duration::from_millis(300)),
+            1.0,
+        );
+        assert_close(
+            accelerator.press(1, start + Duration::from_millis(310)),
+            1.0,
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    addresses::Control,
+    buttons::Button,
+    comms::{Receiver, Sender},
+    manager::Manager,
+    snapshots::{self, SnapshotControl},
+};
+
+#[derive(Error, Debug)]
+#[error("the mixer doesn't support the {0} action")]
+struct UnsupportedActionError(String);
+
+#[derive(Error, Debug)]
+#[error("fade out minutes must be a finite number no less than 0")]
+struct FadeOutMinutesError;
+
+/// Converts the minutes that the volume is faded out over (e.g. by the sleep timer) into a
+/// duration.
+///
+/// # Errors
+///
+/// Returns an error if the minutes are negative, not finite or too large for a duration.
+pub fn fade_duration(minutes: f32) -> Result<Duration> {
+    Ok(Duration::try_from_secs_f32(minutes * 60.0).map_err(|_| FadeOutMinutesError)?)
+}
+
+/// An operation that may be bound to a hotkey or a tray menu item and performed on the device.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    IncreaseVolume,
+    DecreaseVolume,
+    IncreaseVolumeFine,
+    DecreaseVolumeFine,
+    ToggleDim,
+    ToggleMute,
+    ToggleButton { button: Button },
+    SetButton { button: Button, active: bool },
+    SetVolume { volume: f32 },
+    SetVolumeDb { db: f32 },
+    SetVolumePercent { percent: f32 },
+    FadeOut { minutes: f32 },
+    CancelFade,
+    IncreaseChannelVolume { channel: String },
+    DecreaseChannelVolume { channel: String },
+    SetChannelVolumeDb { channel: String, db: f32 },
+    ToggleChannelMute { channel: String },
+    ToggleChannelSolo { channel: String },
+    RecallPreset { preset: String },
+    ToggleReference,
+    RecallSnapshot { snapshot: usize },
+}
+
+impl Action {
+    /// Performs the action, returning whether anything was sent to the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel or preset that the action refers to doesn't exist, the
+    /// snapshot is out of range or a message couldn't be sent to the device.
+    pub fn perform<S: Sender, R: Receiver>(&self, manager: &Manager<S, R>) -> Result<bool> {
+        match *self {
+            Self::IncreaseVolume => manager.increase_volume(),
+            Self::DecreaseVolume => manager.decrease_volume(),
+            Self::IncreaseVolumeFine => manager.increase_volume_fine(),
+            Self::DecreaseVolumeFine => manager.decrease_volume_fine(),
+            Self::ToggleDim => manager.toggle_dim(),
+            Self::ToggleMute => manager.toggle_mute(),
+            Self::ToggleButton { button } => manager.toggle_button(button),
+            Self::SetButton { button, active } => manager.set_button(button, active),
+            Self::SetVolume { volume } => manager.set_volume(volume),
+            Self::SetVolumeDb { db } => manager.set_volume_db(db),
+            Self::SetVolumePercent { percent } => manager.set_volume_percent(percent),
+            Self::FadeOut { minutes } => manager.fade_out(fade_duration(minutes)?),
+            Self::CancelFade => Ok(manager.cancel_ramp()),
+            Self::IncreaseChannelVolume { ref channel } => {
+                manager.increase_channel_volume(manager.channel_id(channel)?)
+            }
+            Self::DecreaseChannelVolume { ref channel } => {
+                manager.decrease_channel_volume(manager.channel_id(channel)?)
+            }
+            Self::SetChannelVolumeDb { ref channel, db } => {
+                manager.set_channel_volume_db(manager.channel_id(channel)?, db)
+            }
+            Self::ToggleChannelMute { ref channel } => {
+                manager.toggle_channel_mute(manager.channel_id(channel)?)
+            }
+            Self::ToggleChannelSolo { ref channel } => {
+                manager.toggle_channel_solo(manager.channel_id(channel)?)
+            }
+            Self::RecallPreset { ref preset } => manager.recall_preset(preset),
+            Self::ToggleReference => manager.toggle_reference(),
+            Self::RecallSnapshot { snapshot } => manager.recall_snapshot(snapshot),
+        }
+    }
+
+    /// Checks that the channel, preset or snapshot that the action refers to exists and that the
+    /// mixer supports the action.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first problem that is found.
+    pub fn validate<S: Sender, R: Receiver>(&self, manager: &Manager<S, R>) -> Result<()> {
+        if let Some(channel) = self.channel() {
+            manager.channel_id(channel)?;
+        }
+        if let Self::RecallPreset { preset } = self {
+            manager.preset(preset)?;
+        }
+        if let Self::RecallSnapshot { snapshot } = *self {
+            snapshots::check_snapshot(snapshot)?;
+        }
+        if let Some(control) = self.control() {
+            if !manager.supports(control) {
+                return Err(UnsupportedActionError(format!("{self:?}")).into());
+            }
+        }
+        Ok(())
+    }
+
+    // The control that the action requires beyond the volume.
+    fn control(&self) -> Option<Control> {
+        match *self {
+            Self::ToggleDim => Some(Control::Button(Button::Dim)),
+            Self::ToggleButton { button } | Self::SetButton { button, .. } => {
+                Some(Control::Button(button))
+            }
+            Self::RecallSnapshot { snapshot } => {
+                Some(Control::Snapshot(SnapshotControl::Recall(snapshot)))
+            }
+            _ => self.channel().map(|_| Control::BankStart),
+        }
+    }
+
+    /// The name of the channel that the action applies to, if any.
+    #[must_use]
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            Self::IncreaseChannelVolume { channel }
+            | Self::DecreaseChannelVolume { channel }
+            | Self::SetChannelVolumeDb { channel, .. }
+            | Self::ToggleChannelMute { channel }
+            | Self::ToggleChannelSolo { channel } => Some(channel),
+            _ => None,
+        }
+    }
+
+    /// Performs the action with its step multiplied by the given amount, which only applies to
+    /// the coarse volume steps so that the fine steps remain precise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`Action::perform`].
+    pub fn perform_scaled<S: Sender, R: Receiver>(
+        &self,
+        manager: &Manager<S, R>,
+        multiplier: f32,
+    ) -> Result<bool> {
+        match self {
+            Self::IncreaseVolume => manager.increase_volume_scaled(multiplier),
+            Self::DecreaseVolume => manager.decrease_volume_scaled(multiplier),
+            _ => self.perform(manager),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        channels::{Bus, ChannelId},
+        config::{Config, HotKeyBinding, Modifier},
+        floats::RoughEq,
+        testing::connect,
+    };
+
+    use super::*;
+
+    #[test]
+    fn increase_named_channel_volume() {
+        let (simulator, mut manager) = connect();
+        let phones = ChannelId {
+            bus: Bus::Output,
+            number: 3,
+        };
+        manager.add_channel("Phones", phones).unwrap();
+        let action = Action::IncreaseChannelVolume {
+            channel: "Phones".to_string(),
+        };
+
+        // The volume of the channel is only known once the channel has been shown.
+        assert!(!action.perform(&manager).unwrap());
+        for _ in 0..2 {
+            assert!(simulator.handle_next().unwrap());
+            assert!(manager.recieve_volume().unwrap());
+        }
+        assert!(action.perform(&manager).unwrap());
+        for _ in 0..3 {
+            assert!(simulator.handle_next().unwrap());
+        }
+        assert!(simulator.channel_volume(phones).roughly_eq(0.02));
+
+        let unknown = Action::ToggleChannelSolo {
+            channel: "Speakers".to_string(),
+        };
+        assert!(unknown.perform(&manager).is_err());
+    }
+
+    #[test]
+    fn invalid_fade_out_minutes() {
+        assert_eq!(fade_duration(1.5).unwrap(), Duration::from_secs(90));
+        for minutes in [-1.0, f32::INFINITY, f32::NAN, 1e30] {
+            assert!(fade_duration(minutes).is_err(), "{minutes}");
+        }
+
+        let config: Config = toml::from_str(
+            "
+            [tray]
+            sleep_timer_minutes = [15.0, inf]
+            ",
+        )
+        .unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn deserialize_hotkeys() {
+        let config: Config = toml::from_str(
+            r#"
+            [[hotkeys]]
+            key = "F13"
+            modifiers = ["ctrl", "shift"]
+            action = "set_volume_db"
+            db = -20.0
+
+            [[hotkeys]]
+            key = "F14"
+            action = "set_volume_percent"
+            percent = 50
+
+            [[hotkeys]]
+            key = "F15"
+            action = "toggle_dim"
+
+            [[hotkeys]]
+            key = "F16"
+            action = "set_button"
+            button = "speaker_b"
+            active = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.hotkeys,
+            vec![
+                HotKeyBinding {
+                    key: "F13".to_string(),
+                    modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+                    action: Action::SetVolumeDb { db: -20.0 },
+                },
+                HotKeyBinding {
+                    key: "F14".to_string(),
+                    modifiers: vec![],
+                    action: Action::SetVolumePercent { percent: 50.0 },
+                },
+                HotKeyBinding {
+                    key: "F15".to_string(),
+                    modifiers: vec![],
+                    action: Action::ToggleDim,
+                },
+                HotKeyBinding {
+                    key: "F16".to_string(),
+                    modifiers: vec![],
+                    action: Action::SetButton {
+                        button: Button::SpeakerB,
+                        active: true,
+                    },
+                },
+            ]
+        );
+    }
+}
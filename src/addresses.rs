@@ -0,0 +1,356 @@
+use anyhow::Result;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    buttons::Button,
+    channels::{Bus, Strip},
+    meters::Meter,
+    snapshots::{SnapshotControl, SNAPSHOT_COUNT},
+};
+
+/// The placeholder for the number of the strip or snapshot in numbered addresses.
+const NUMBER_PLACEHOLDER: &str = "{n}";
+
+/// Creates the control for the number found in a numbered address.
+type NumberedControl = fn(usize) -> Control;
+
+#[derive(Error, Debug)]
+#[error("OSC address {0:?} must start with a slash")]
+struct AddressSlashError(String);
+
+#[derive(Error, Debug)]
+#[error("OSC address {0:?} must contain {{n}} exactly once")]
+struct AddressPlaceholderError(String);
+
+#[derive(Error, Debug)]
+#[error("OSC address {0:?} must not contain {{n}}")]
+struct AddressUnexpectedPlaceholderError(String);
+
+#[derive(Error, Debug)]
+#[error("OSC address {0:?} is used for more than one control")]
+struct AddressDuplicateError(String);
+
+/// A control of the device that the manager sends to or receives from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Volume,
+    VolumeDb,
+    Button(Button),
+    Meter(Meter),
+    Bus(Bus),
+    BankStart,
+    Strip(Strip),
+    Snapshot(SnapshotControl),
+    /// The mute of the main output on mixers that have one, where 1.0 is muted.
+    Mute,
+}
+
+/// The OSC addresses of each control, which default to those used by `TotalMix` FX.  The addresses
+/// of strips and snapshots contain {n} in place of their number.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Addresses {
+    pub volume: String,
+    pub volume_db: String,
+    pub dim: String,
+    pub mono: String,
+    pub speaker_b: String,
+    pub talkback: String,
+    pub external_input: String,
+    pub recall: String,
+    pub level_left: String,
+    pub level_right: String,
+    pub peak_left: String,
+    pub peak_right: String,
+    pub bus_input: String,
+    pub bus_playback: String,
+    pub bus_output: String,
+    pub bank_start: String,
+    pub strip_volume: String,
+    pub strip_volume_db: String,
+    pub strip_name: String,
+    pub strip_mute: String,
+    pub strip_solo: String,
+    pub snapshot: String,
+    pub snapshot_name: String,
+}
+
+impl Default for Addresses {
+    fn default() -> Self {
+        Self {
+            volume: "/1/mastervolume".to_string(),
+            volume_db: "/1/mastervolumeVal".to_string(),
+            dim: "/1/mainDim".to_string(),
+            mono: "/1/mainMono".to_string(),
+            speaker_b: "/1/mainSpeakerB".to_string(),
+            talkback: "/1/mainTalkback".to_string(),
+            external_input: "/1/mainExtIn".to_string(),
+            recall: "/1/mainRecall".to_string(),
+            level_left: "/1/mainLevelLeft".to_string(),
+            level_right: "/1/mainLevelRight".to_string(),
+            peak_left: "/1/mainPeakLeft".to_string(),
+            peak_right: "/1/mainPeakRight".to_string(),
+            bus_input: "/1/busInput".to_string(),
+            bus_playback: "/1/busPlayback".to_string(),
+            bus_output: "/1/busOutput".to_string(),
+            bank_start: "/setBankStart".to_string(),
+            strip_volume: "/1/volume{n}".to_string(),
+            strip_volume_db: "/1/volume{n}Val".to_string(),
+            strip_name: "/1/trackname{n}".to_string(),
+            strip_mute: "/1/mute/1/{n}".to_string(),
+            strip_solo: "/1/solo/1/{n}".to_string(),
+            snapshot: "/3/snapshots/{n}/1".to_string(),
+            snapshot_name: "/3/snapshotName{n}".to_string(),
+        }
+    }
+}
+
+impl Addresses {
+    /// Checks that every address is well formed and that no two controls share an address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first address that is malformed or shared.
+    pub fn validate(&self) -> Result<()> {
+        let fixed = self.fixed();
+        for (_, addr) in &fixed {
+            if !addr.starts_with('/') {
+                return Err(AddressSlashError(addr.to_string()).into());
+            }
+            if addr.contains(NUMBER_PLACEHOLDER) {
+                return Err(AddressUnexpectedPlaceholderError(addr.to_string()).into());
+            }
+            if fixed.iter().filter(|(_, other)| other == addr).count() > 1
+                || self.numbered_control(addr).is_some()
+            {
+                return Err(AddressDuplicateError(addr.to_string()).into());
+            }
+        }
+
+        let numbered = self.numbered();
+        for (_, template) in &numbered {
+            if !template.starts_with('/') {
+                return Err(AddressSlashError(template.to_string()).into());
+            }
+            if template.matches(NUMBER_PLACEHOLDER).count() != 1 {
+                return Err(AddressPlaceholderError(template.to_string()).into());
+            }
+            if numbered
+                .iter()
+                .filter(|(_, other)| other == template)
+                .count()
+                > 1
+            {
+                return Err(AddressDuplicateError(template.to_string()).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The address that the control is sent to and received from, if it has one.
+    #[must_use]
+    pub fn osc_addr(&self, control: Control) -> Option<String> {
+        let numbered = |template: &str, number: usize| {
+            template.replace(NUMBER_PLACEHOLDER, &number.to_string())
+        };
+        let addr = match control {
+            Control::Strip(strip) => {
+                let (template, number) = self.strip_template(strip);
+                numbered(template, number)
+            }
+            Control::Snapshot(SnapshotControl::Recall(snapshot)) => {
+                numbered(&self.snapshot, snapshot)
+            }
+            Control::Snapshot(SnapshotControl::Name(snapshot)) => {
+                numbered(&self.snapshot_name, snapshot)
+            }
+            control => self
+                .fixed()
+                .into_iter()
+                .find_map(|(other, addr)| (other == control).then(|| addr.to_string()))?,
+        };
+        Some(addr)
+    }
+
+    /// The control that the address belongs to, if any.
+    #[must_use]
+    pub fn control(&self, addr: &str) -> Option<Control> {
+        if let Some((control, _)) = self.fixed().into_iter().find(|(_, other)| *other == addr) {
+            return Some(control);
+        }
+        self.numbered_control(addr).filter(|control| match control {
+            Control::Snapshot(
+                SnapshotControl::Recall(snapshot) | SnapshotControl::Name(snapshot),
+            ) => *snapshot <= SNAPSHOT_COUNT,
+            _ => true,
+        })
+    }
+
+    // The control of any number that the address belongs to, which may be out of range.
+    fn numbered_control(&self, addr: &str) -> Option<Control> {
+        self.numbered().into_iter().find_map(|(control, template)| {
+            let (prefix, suffix) = template.split_once(NUMBER_PLACEHOLDER)?;
+            let number = addr
+                .strip_prefix(prefix)?
+                .strip_suffix(suffix)?
+                .parse()
+                .ok()
+                .filter(|number| *number > 0)?;
+            Some(control(number))
+        })
+    }
+
+    fn fixed(&self) -> [(Control, &str); 16] {
+        [
+            (Control::Volume, &self.volume),
+            (Control::VolumeDb, &self.volume_db),
+            (Control::Button(Button::Dim), &self.dim),
+            (Control::Button(Button::Mono), &self.mono),
+            (Control::Button(Button::SpeakerB), &self.speaker_b),
+            (Control::Button(Button::Talkback), &self.talkback),
+            (Control::Button(Button::ExternalInput), &self.external_input),
+            (Control::Button(Button::Recall), &self.recall),
+            (Control::Meter(Meter::LevelLeft), &self.level_left),
+            (Control::Meter(Meter::LevelRight), &self.level_right),
+            (Control::Meter(Meter::PeakLeft), &self.peak_left),
+            (Control::Meter(Meter::PeakRight), &self.peak_right),
+            (Control::Bus(Bus::Input), &self.bus_input),
+            (Control::Bus(Bus::Playback), &self.bus_playback),
+            (Control::Bus(Bus::Output), &self.bus_output),
+            (Control::BankStart, &self.bank_start),
+        ]
+    }
+
+    fn numbered(&self) -> [(NumberedControl, &str); 7] {
+        [
+            (
+                |strip| Control::Strip(Strip::Volume(strip)),
+                &self.strip_volume,
+            ),
+            (
+                |strip| Control::Strip(Strip::VolumeDb(strip)),
+                &self.strip_volume_db,
+            ),
+            (|strip| Control::Strip(Strip::Name(strip)), &self.strip_name),
+            (|strip| Control::Strip(Strip::Mute(strip)), &self.strip_mute),
+            (|strip| Control::Strip(Strip::Solo(strip)), &self.strip_solo),
+            (
+                |snapshot| Control::Snapshot(SnapshotControl::Recall(snapshot)),
+                &self.snapshot,
+            ),
+            (
+                |snapshot| Control::Snapshot(SnapshotControl::Name(snapshot)),
+                &self.snapshot_name,
+            ),
+        ]
+    }
+
+    fn strip_template(&self, strip: Strip) -> (&str, usize) {
+        match strip {
+            Strip::Volume(number) => (&self.strip_volume, number),
+            Strip::VolumeDb(number) => (&self.strip_volume_db, number),
+            Strip::Name(number) => (&self.strip_name, number),
+            Strip::Mute(number) => (&self.strip_mute, number),
+            Strip::Solo(number) => (&self.strip_solo, number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_totalmix() {
+        let addresses = Addresses::default();
+        addresses.validate().unwrap();
+        for control in [
+            Control::Volume,
+            Control::Button(Button::Talkback),
+            Control::Meter(Meter::PeakRight),
+            Control::Bus(Bus::Playback),
+            Control::BankStart,
+        ] {
+            assert_eq!(
+                addresses.control(&addresses.osc_addr(control).unwrap()),
+                Some(control)
+            );
+        }
+        for (addr, control) in [
+            ("/1/mastervolume", Control::Volume),
+            ("/1/mainExtIn", Control::Button(Button::ExternalInput)),
+            ("/1/mainLevelLeft", Control::Meter(Meter::LevelLeft)),
+            ("/1/busOutput", Control::Bus(Bus::Output)),
+            ("/setBankStart", Control::BankStart),
+            ("/1/volume1", Control::Strip(Strip::Volume(1))),
+            ("/1/volume2Val", Control::Strip(Strip::VolumeDb(2))),
+            ("/1/trackname3", Control::Strip(Strip::Name(3))),
+            ("/1/mute/1/4", Control::Strip(Strip::Mute(4))),
+            ("/1/solo/1/12", Control::Strip(Strip::Solo(12))),
+            (
+                "/3/snapshots/1/1",
+                Control::Snapshot(SnapshotControl::Recall(1)),
+            ),
+            (
+                "/3/snapshotName8",
+                Control::Snapshot(SnapshotControl::Name(8)),
+            ),
+        ] {
+            assert_eq!(addresses.control(addr), Some(control));
+            assert_eq!(addresses.osc_addr(control).unwrap(), addr);
+        }
+        assert_eq!(addresses.control("/1/volume0"), None);
+        assert_eq!(addresses.control("/1/volumeVal"), None);
+        assert_eq!(addresses.control("/3/snapshotName0"), None);
+        assert_eq!(addresses.control("/3/snapshots/9/1"), None);
+        assert_eq!(addresses.control("/2/mastervolume"), None);
+    }
+
+    #[test]
+    fn custom() {
+        let addresses: Addresses = toml::from_str(
+            r#"
+            volume = "/2/mastervolume"
+            strip_volume = "/2/volume/{n}"
+            "#,
+        )
+        .unwrap();
+        addresses.validate().unwrap();
+        assert_eq!(addresses.control("/2/mastervolume"), Some(Control::Volume));
+        assert_eq!(addresses.control("/1/mastervolume"), None);
+        assert_eq!(
+            addresses
+                .osc_addr(Control::Strip(Strip::Volume(3)))
+                .unwrap(),
+            "/2/volume/3"
+        );
+        assert_eq!(addresses.osc_addr(Control::Mute), None);
+        assert_eq!(
+            addresses.control("/2/volume/3"),
+            Some(Control::Strip(Strip::Volume(3)))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let invalid = [
+            r#"volume = "1/mastervolume""#,
+            r#"volume = "/1/master{n}""#,
+            r#"strip_mute = "/1/mute""#,
+            r#"strip_mute = "/1/mute/{n}/{n}""#,
+            r#"dim = "/1/mainMono""#,
+            r#"strip_solo = "/1/mute/1/{n}""#,
+            r#"volume = "/1/volume1""#,
+            r#"snapshot_name = "/1/mainDim{n}"
+               dim = "/1/mainDim2""#,
+        ];
+        for addresses in invalid {
+            let addresses: Addresses = toml::from_str(addresses).unwrap();
+            assert!(addresses.validate().is_err(), "{addresses:?}");
+        }
+
+        // A misspelt control would otherwise silently keep its default address.
+        assert!(toml::from_str::<Addresses>(r#"mastervolume = "/2/mastervolume""#).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::{fmt, time::Duration};
+
+use anyhow::Result;
+use rosc::{OscMessage, OscType};
+
+use crate::{
+    addresses::{Addresses, Control},
+    buttons::Button,
+    fader,
+};
+
+/// The protocol spoken by a mixer, which translates between OSC messages and the controls of the
+/// manager.
+pub trait MixerBackend: fmt::Debug + Send + Sync {
+    /// The messages that request the current state of the device.
+    fn request(&self) -> Vec<OscMessage>;
+
+    /// Decodes a message received from the device into the control that it updates and its value,
+    /// which is expressed as it would be by `TotalMix` (e.g. 1.0 for an active button).
+    fn decode(&self, message: &OscMessage) -> Option<(Control, OscType)>;
+
+    /// Encodes a write of the value to the control, returning None if the device lacks the control.
+    fn encode(&self, control: Control, value: f32) -> Option<OscMessage>;
+
+    fn supports(&self, control: Control) -> bool {
+        self.encode(control, 0.0).is_some()
+    }
+
+    /// How often the device must be reminded to keep sending updates, if at all.
+    fn keep_alive_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    fn keep_alive(&self) -> Vec<OscMessage> {
+        Vec::new()
+    }
+
+    /// Whether the device replies to the address that messages were sent from rather than to a
+    /// configured address.
+    fn replies_to_sender(&self) -> bool {
+        false
+    }
+
+    /// Converts a normalised fader position into decibels using the fader law of the device.
+    fn volume_to_db(&self, volume: f32) -> f32 {
+        fader::volume_to_db(volume)
+    }
+
+    fn db_to_volume(&self, db: f32) -> f32 {
+        fader::db_to_volume(db)
+    }
+
+    /// The lowest level that may be reached by stepping the volume down, below which the volume is
+    /// considered to be -oo.
+    fn min_db(&self) -> f32 {
+        fader::MIN_DB
+    }
+}
+
+/// The value of a numeric argument of any OSC type, where booleans are 1.0 when true.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+#[must_use]
+pub const fn number(arg: &OscType) -> Option<f32> {
+    match *arg {
+        OscType::Float(value) => Some(value),
+        OscType::Double(value) => Some(value as f32),
+        OscType::Int(value) => Some(value as f32),
+        OscType::Long(value) => Some(value as f32),
+        OscType::Bool(value) => Some(if value { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// `TotalMix` FX, which is controlled through the addresses of its OSC pages.
+#[derive(Debug, Default)]
+pub struct TotalMix {
+    addresses: Addresses,
+}
+
+impl TotalMix {
+    /// Creates the backend for `TotalMix` FX with the given addresses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the addresses aren't valid.
+    pub fn new(addresses: Addresses) -> Result<Self> {
+        addresses.validate()?;
+        Ok(Self { addresses })
+    }
+}
+
+impl MixerBackend for TotalMix {
+    // TotalMix replies to a negative value with the current state of the control.
+    fn request(&self) -> Vec<OscMessage> {
+        let mut controls = vec![Control::Volume];
+        controls.extend(Button::ALL.map(Control::Button));
+        controls
+            .into_iter()
+            .filter_map(|control| self.encode(control, -1.0))
+            .collect()
+    }
+
+    fn decode(&self, message: &OscMessage) -> Option<(Control, OscType)> {
+        let control = self.addresses.control(&message.addr)?;
+        Some((control, message.args.first()?.clone()))
+    }
+
+    fn encode(&self, control: Control, value: f32) -> Option<OscMessage> {
+        Some(OscMessage {
+            addr: self.addresses.osc_addr(control)?,
+            args: vec![OscType::Float(value)],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totalmix() {
+        let backend = TotalMix::default();
+        assert_eq!(backend.request().len(), Button::ALL.len() + 1);
+        assert_eq!(
+            backend.decode(&OscMessage {
+                addr: "/1/mainDim".to_string(),
+                args: vec![OscType::Float(1.0)],
+            }),
+            Some((Control::Button(Button::Dim), OscType::Float(1.0)))
+        );
+        assert_eq!(
+            backend.encode(Control::Volume, 0.5),
+            Some(OscMessage {
+                addr: "/1/mastervolume".to_string(),
+                args: vec![OscType::Float(0.5)],
+            })
+        );
+        assert!(!backend.supports(Control::Mute));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(&OscType::Double(0.5)), Some(0.5));
+        assert_eq!(number(&OscType::Int(1)), Some(1.0));
+        assert_eq!(number(&OscType::Long(-20)), Some(-20.0));
+        assert_eq!(number(&OscType::Bool(true)), Some(1.0));
+        assert_eq!(number(&OscType::String("1".to_string())), None);
+    }
+}
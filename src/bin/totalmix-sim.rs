@@ -0,0 +1,43 @@
+use std::{env, net::SocketAddrV4};
+
+use anyhow::{Context, Result};
+use totalmix_volume_control::{config::Config, sim::Simulator};
+
+fn main() -> Result<()> {
+    // The simulator listens where TotalMix Volume Control sends and replies to where it receives.
+    let config = Config::default();
+    let mut listen_addr = SocketAddrV4::new(
+        config.osc.outgoing_hostname.parse()?,
+        config.osc.outgoing_port,
+    );
+    let mut reply_addr = SocketAddrV4::new(
+        config.osc.incoming_hostname.parse()?,
+        config.osc.incoming_port,
+    );
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                listen_addr = args
+                    .next()
+                    .context("--listen requires an address")?
+                    .parse()?;
+            }
+            "--reply" => {
+                reply_addr = args
+                    .next()
+                    .context("--reply requires an address")?
+                    .parse()?;
+            }
+            _ => {
+                eprintln!("usage: totalmix-sim [--listen <host:port>] [--reply <host:port>]");
+                return Ok(());
+            }
+        }
+    }
+
+    let simulator = Simulator::bind(listen_addr, reply_addr)?;
+    println!("Simulating TotalMix FX on {listen_addr} (replying to {reply_addr})");
+    simulator.run()
+}
@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::floats::RoughEq;
+
+// A press of a button is verified once the device has had time to respond, pressing it again (or
+// requesting its state again) up to a limited number of attempts.
+const PRESS_CONFIRM_TIMEOUT: Duration = Duration::from_millis(500);
+const MAX_PRESS_ATTEMPTS: u32 = 3;
+
+/// The buttons in the Control Room section of `TotalMix`, each of which toggles when pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Button {
+    Dim,
+    Mono,
+    SpeakerB,
+    Talkback,
+    ExternalInput,
+    Recall,
+}
+
+impl Button {
+    pub const ALL: [Self; 6] = [
+        Self::Dim,
+        Self::Mono,
+        Self::SpeakerB,
+        Self::Talkback,
+        Self::ExternalInput,
+        Self::Recall,
+    ];
+
+    /// The text used to indicate that the button is active.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Dim => "Dim",
+            Self::Mono => "Mono",
+            Self::SpeakerB => "Speaker B",
+            Self::Talkback => "Talkback",
+            Self::ExternalInput => "Ext In",
+            Self::Recall => "Recall",
+        }
+    }
+}
+
+/// A press of a button that hasn't been verified yet, along with the latest state that the device
+/// reported since.
+#[derive(Debug, Clone, Copy)]
+struct PendingPress {
+    active: bool,
+    sent: Instant,
+    attempts: u32,
+    received: Option<bool>,
+}
+
+/// The state of a button as reported by the device, which is unknown until the device reports it.
+#[derive(Debug, Clone, Copy)]
+pub struct ButtonState {
+    value: f32,
+    pending: Option<PendingPress>,
+}
+
+impl Default for ButtonState {
+    fn default() -> Self {
+        Self {
+            value: -1.0,
+            pending: None,
+        }
+    }
+}
+
+impl ButtonState {
+    #[must_use]
+    pub fn known(&self) -> bool {
+        self.value.roughly_ne(-1.0)
+    }
+
+    #[must_use]
+    pub fn active(&self) -> bool {
+        self.value.roughly_eq(1.0)
+    }
+
+    /// The state the button is expected to be in once any press of it is verified.
+    #[must_use]
+    pub fn expected_active(&self) -> bool {
+        self.pending
+            .map_or_else(|| self.active(), |pending| pending.active)
+    }
+
+    /// Records the state reported by the device, returning whether it changed and whether a press
+    /// of the button was awaiting verification.
+    pub fn receive(&mut self, value: f32) -> (bool, bool) {
+        let changed = self.value.roughly_ne(value);
+        self.value = value;
+        let pending = self.pending.as_mut().map_or(false, |pending| {
+            pending.received = Some(value.roughly_eq(1.0));
+            true
+        });
+        (changed, pending)
+    }
+
+    /// Records a press of the button if it isn't already in (or about to be in) the given state,
+    /// returning whether the button should be pressed.
+    pub fn press(&mut self, active: bool, now: Instant) -> bool {
+        if active == self.expected_active() {
+            return false;
+        }
+        self.pending = Some(PendingPress {
+            active,
+            sent: now,
+            attempts: 1,
+            received: None,
+        });
+        true
+    }
+
+    /// Verifies a press of the button once the device has had time to respond, returning the value
+    /// to send when the button should be pressed again (because the device reported that it is in
+    /// the wrong state) or its state requested again (because the device didn't report it at all).
+    pub fn verify(&mut self, now: Instant) -> Option<f32> {
+        let pending = self.pending.as_mut()?;
+        if now.duration_since(pending.sent) < PRESS_CONFIRM_TIMEOUT {
+            return None;
+        }
+
+        if pending.received == Some(pending.active) || pending.attempts >= MAX_PRESS_ATTEMPTS {
+            self.pending = None;
+            return None;
+        }
+
+        pending.sent = now;
+        pending.attempts += 1;
+        Some(if pending.received.take().is_some() {
+            1.0
+        } else {
+            -1.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_only_when_needed() {
+        let mut state = ButtonState::default();
+        let now = Instant::now();
+        assert_eq!(state.receive(0.0), (true, false));
+        assert!(!state.press(false, now));
+        assert!(state.press(true, now));
+        assert!(!state.press(true, now));
+        assert!(state.expected_active());
+        assert!(!state.active());
+    }
+
+    #[test]
+    fn verify_confirmed_press() {
+        let mut state = ButtonState::default();
+        let now = Instant::now();
+        state.receive(0.0);
+        assert!(state.press(true, now));
+        assert_eq!(state.receive(1.0), (true, true));
+        assert_eq!(state.verify(now), None);
+        assert_eq!(state.verify(now + PRESS_CONFIRM_TIMEOUT), None);
+        assert_eq!(state.receive(1.0), (false, false));
+    }
+
+    #[test]
+    fn verify_retries_press() {
+        let mut state = ButtonState::default();
+        let now = Instant::now();
+        state.receive(0.0);
+        assert!(state.press(true, now));
+        state.receive(0.0);
+        let later = now + PRESS_CONFIRM_TIMEOUT;
+        assert_eq!(state.verify(later), Some(1.0));
+        assert_eq!(state.verify(later + PRESS_CONFIRM_TIMEOUT), Some(-1.0));
+        assert_eq!(state.verify(later + PRESS_CONFIRM_TIMEOUT * 2), None);
+        assert!(!state.expected_active());
+    }
+}
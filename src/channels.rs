@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use rosc::OscType;
+use serde::Deserialize;
+
+/// The rows of channels in the `TotalMix` mixer, only one of which is shown on page 1 at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bus {
+    Input,
+    Playback,
+    Output,
+}
+
+impl Bus {
+    pub const ALL: [Self; 3] = [Self::Input, Self::Playback, Self::Output];
+}
+
+/// A channel of the mixer, numbered from 1 within its bus as in `TotalMix`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub struct ChannelId {
+    pub bus: Bus,
+    pub number: usize,
+}
+
+impl ChannelId {
+    /// The most channels in a bus of any interface supported by `TotalMix` (the `MADIface XT`).
+    pub const MAX_NUMBER: usize = 198;
+
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        self.number >= 1 && self.number <= Self::MAX_NUMBER
+    }
+}
+
+/// The state of a channel, which is only known once the channel has been shown on page 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub name: Option<String>,
+    pub volume: f32,
+    pub volume_db: Option<String>,
+    pub muted: bool,
+    pub soloed: bool,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Self {
+            name: None,
+            volume: -1.0,
+            volume_db: None,
+            muted: false,
+            soloed: false,
+        }
+    }
+}
+
+/// The controls of each channel strip on page 1, where the strips are numbered from 1 within the
+/// bank that is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strip {
+    Volume(usize),
+    VolumeDb(usize),
+    Name(usize),
+    Mute(usize),
+    Solo(usize),
+}
+
+/// The bus and bank shown on page 1 along with the state of every channel that has been shown.
+#[derive(Debug)]
+pub struct Mixer {
+    bus: Option<Bus>,
+    bank_start: Option<usize>,
+    bank_size: usize,
+    channels: HashMap<ChannelId, Channel>,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            bus: None,
+            bank_start: None,
+            bank_size: 8,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+impl Mixer {
+    #[must_use]
+    pub const fn bus(&self) -> Option<Bus> {
+        self.bus
+    }
+
+    #[must_use]
+    pub const fn bank_start(&self) -> Option<usize> {
+        self.bank_start
+    }
+
+    pub const fn set_bank_size(&mut self, bank_size: usize) {
+        self.bank_size = bank_size;
+    }
+
+    #[must_use]
+    pub fn channel(&self, channel: ChannelId) -> Option<&Channel> {
+        self.channels.get(&channel)
+    }
+
+    pub const fn select_bus(&mut self, bus: Bus) {
+        self.bus = Some(bus);
+    }
+
+    pub const fn select_bank(&mut self, bank_start: usize) {
+        self.bank_start = Some(bank_start);
+    }
+
+    /// The start of the bank that contains the channel.
+    #[must_use]
+    pub const fn bank_start_of(&self, channel: ChannelId) -> usize {
+        (channel.number.saturating_sub(1) / self.bank_size) * self.bank_size
+    }
+
+    pub fn set_volume(&mut self, channel: ChannelId, volume: f32) {
+        self.channels.entry(channel).or_default().volume = volume;
+    }
+
+    /// Records a message received for a strip, returning the channel shown on it and its new state
+    /// if it changed.
+    pub fn receive(&mut self, strip: Strip, arg: &OscType) -> Option<(ChannelId, Channel)> {
+        let number = match strip {
+            Strip::Volume(number)
+            | Strip::VolumeDb(number)
+            | Strip::Name(number)
+            | Strip::Mute(number)
+            | Strip::Solo(number) => number,
+        };
+        let channel_id = ChannelId {
+            bus: self.bus?,
+            number: self.bank_start? + number,
+        };
+        let channel = self.channels.entry(channel_id).or_default();
+        let previous = channel.clone();
+        match (strip, arg) {
+            (Strip::Volume(_), OscType::Float(volume)) => channel.volume = *volume,
+            (Strip::VolumeDb(_), OscType::String(volume_db)) => {
+                channel.volume_db = Some(volume_db.clone());
+            }
+            (Strip::Name(_), OscType::String(name)) => channel.name = Some(name.clone()),
+            (Strip::Mute(_), OscType::Float(muted)) => channel.muted = *muted >= 0.5,
+            (Strip::Solo(_), OscType::Float(soloed)) => channel.soloed = *soloed >= 0.5,
+            _ => return None,
+        }
+        (*channel != previous).then(|| (channel_id, channel.clone()))
+    }
+
+    /// Forgets everything known about the mixer other than the bank size.
+    pub fn clear(&mut self) {
+        self.bus = None;
+        self.bank_start = None;
+        self.channels.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_start_of_channel() {
+        let mixer = Mixer::default();
+        let channel = |number| ChannelId {
+            bus: Bus::Output,
+            number,
+        };
+        assert_eq!(mixer.bank_start_of(channel(1)), 0);
+        assert_eq!(mixer.bank_start_of(channel(8)), 0);
+        assert_eq!(mixer.bank_start_of(channel(11)), 8);
+    }
+
+    #[test]
+    fn receive() {
+        let mut mixer = Mixer::default();
+        assert_eq!(mixer.receive(Strip::Volume(1), &OscType::Float(0.5)), None);
+
+        mixer.select_bus(Bus::Playback);
+        mixer.select_bank(8);
+        let channel = ChannelId {
+            bus: Bus::Playback,
+            number: 10,
+        };
+        let (channel_id, state) = mixer
+            .receive(Strip::Name(2), &OscType::String("PH 3/4".to_string()))
+            .unwrap();
+        assert_eq!(channel_id, channel);
+        assert_eq!(state.name.as_deref(), Some("PH 3/4"));
+        assert!(
+            mixer
+                .receive(Strip::Mute(2), &OscType::Float(1.0))
+                .unwrap()
+                .1
+                .muted
+        );
+        assert_eq!(mixer.receive(Strip::Mute(2), &OscType::Float(1.0)), None);
+        assert!(mixer.channel(channel).unwrap().muted);
+    }
+}
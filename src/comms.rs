@@ -1,5 +1,6 @@
 use std::{
-    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
     time::Duration,
 };
 
@@ -7,11 +8,21 @@ use anyhow::Result;
 use rosc::OscPacket;
 
 pub trait Sender {
+    /// Sends the packet to the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet couldn't be encoded or sent.
     fn send(&self, packet: &OscPacket) -> Result<()>;
 }
 
 pub trait Receiver {
-    fn receive(&self) -> Result<OscPacket>;
+    /// Receives the next packet from the device, returning None if nothing usable arrived in time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket couldn't be read.
+    fn receive(&self) -> Result<Option<OscPacket>>;
 }
 
 pub struct UdpSender {
@@ -20,6 +31,11 @@ pub struct UdpSender {
 }
 
 impl UdpSender {
+    /// Creates a sender that sends to the given address from an ephemeral port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket couldn't be bound.
     pub fn new(addr: SocketAddrV4) -> Result<Self> {
         let bind_addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 0);
         Ok(Self {
@@ -42,18 +58,77 @@ pub struct UdpReceiver {
 }
 
 impl UdpReceiver {
+    /// Binds a receiver to the given address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket couldn't be bound or configured.
     pub fn bind(addr: SocketAddrV4) -> Result<Self> {
         let socket = UdpSocket::bind(addr)?;
-        socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+        socket.set_read_timeout(Some(Duration::from_millis(500)))?;
         Ok(Self { socket })
     }
+
+    /// Sets how long to wait for each packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timeout couldn't be applied to the socket.
+    pub fn set_read_timeout(&self, timeout: Duration) -> Result<()> {
+        self.socket.set_read_timeout(Some(timeout))?;
+        Ok(())
+    }
+
+    /// The address that the receiver is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address of the socket couldn't be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Creates a sender that sends from the socket of this receiver, so that devices which reply
+    /// to the sender are heard.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket couldn't be cloned.
+    pub fn sender(&self, addr: SocketAddrV4) -> Result<UdpSender> {
+        Ok(UdpSender {
+            socket: self.socket.try_clone()?,
+            addr,
+        })
+    }
 }
 
 impl Receiver for UdpReceiver {
-    fn receive(&self) -> Result<OscPacket> {
+    fn receive(&self) -> Result<Option<OscPacket>> {
         let mut buf = [0u8; rosc::decoder::MTU];
-        let (size, _) = self.socket.recv_from(&mut buf)?;
-        let (_, packet) = rosc::decoder::decode_udp(&buf[..size])?;
-        Ok(packet)
+        let size = match self.socket.recv_from(&mut buf) {
+            Ok((size, _)) => size,
+            // Timeouts simply mean that nothing was sent, and Windows reports a connection reset
+            // when a previous send couldn't be delivered (e.g. TotalMix isn't running).
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::ConnectionReset
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        // Packets that aren't valid OSC (e.g. stray traffic on the port) are skipped rather than
+        // treated as a failure to communicate with the device.
+        match rosc::decoder::decode_udp(&buf[..size]) {
+            Ok((_, packet)) => Ok(Some(packet)),
+            Err(error) => {
+                eprintln!("ignoring a packet that couldn't be decoded: {error}");
+                Ok(None)
+            }
+        }
     }
 }
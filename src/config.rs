@@ -1,23 +1,79 @@
-use std::{fs, path::PathBuf, str::FromStr};
+#[cfg(not(windows))]
+use std::env;
+use std::{fs, io, path::PathBuf, time::Duration};
 
 use anyhow::Result;
 use hex_color::HexColor;
 use serde::Deserialize;
+use thiserror::Error;
+#[cfg(windows)]
 use windows::Win32::UI::Shell::{FOLDERID_RoamingAppData, SHGetKnownFolderPath, KF_FLAG_DEFAULT};
 
+use crate::{
+    acceleration::AccelerationCurve,
+    actions::{self, Action},
+    addresses::Addresses,
+    channels::{Bus, ChannelId},
+    presets::Preset,
+    ramp::Curve,
+};
+
+#[cfg(not(windows))]
+#[derive(Error, Debug)]
+#[error("unable to determine the user configuration directory")]
+struct ConfigDirError;
+
+#[derive(Error, Debug)]
+#[error("the {0} must be a finite number of seconds no less than 0")]
+struct SecondsError(&'static str);
+
+/// Loads the configuration of the user, which is the default configuration until the user creates
+/// a configuration file.
+///
+/// # Errors
+///
+/// Returns an error if the configuration file exists but couldn't be read, parsed or validated.
 pub fn get_user_config() -> Result<Config> {
     let config_path = get_default_config_path()?;
-    let config = fs::read_to_string(&config_path)?;
-    Ok(toml::from_str::<Config>(&config)?)
+    let config = match fs::read_to_string(&config_path) {
+        Ok(config) => config,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(error) => return Err(error.into()),
+    };
+    let config = toml::from_str::<Config>(&config)?;
+    config.validate()?;
+    Ok(config)
+}
+
+fn seconds(name: &'static str, seconds: f64) -> Result<Duration> {
+    Ok(Duration::try_from_secs_f64(seconds).map_err(|_| SecondsError(name))?)
+}
+
+/// The path of the configuration file of the user.
+///
+/// # Errors
+///
+/// Returns an error if the configuration directory of the user couldn't be determined.
+pub fn get_default_config_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?
+        .join("TotalMix Volume Control")
+        .join("Config.toml"))
 }
 
-fn get_default_config_path() -> Result<PathBuf> {
+#[cfg(windows)]
+fn get_config_dir() -> Result<PathBuf> {
     let path = unsafe {
         SHGetKnownFolderPath(&FOLDERID_RoamingAppData, KF_FLAG_DEFAULT, None)?.to_string()?
     };
-    Ok(PathBuf::from_str(&path)?
-        .join("TotalMix Volume Control")
-        .join("Config.toml"))
+    Ok(PathBuf::from(path))
+}
+
+#[cfg(not(windows))]
+fn get_config_dir() -> Result<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(|| ConfigDirError.into())
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,6 +83,13 @@ pub struct Osc {
     pub outgoing_port: u16,
     pub incoming_hostname: String,
     pub incoming_port: u16,
+    pub device_timeout: f64,
+    pub reconnect_interval: f64,
+    pub max_reconnect_interval: f64,
+    pub max_send_rate: f32,
+    pub backend: Backend,
+    pub bank_size: usize,
+    pub addresses: Addresses,
 }
 
 impl Default for Osc {
@@ -36,16 +99,99 @@ impl Default for Osc {
             outgoing_port: 7001,
             incoming_hostname: "127.0.0.1".to_string(),
             incoming_port: 9001,
+            device_timeout: 5.0,
+            reconnect_interval: 1.0,
+            max_reconnect_interval: 30.0,
+            max_send_rate: 50.0,
+            backend: Backend::TotalMix,
+            bank_size: 8,
+            addresses: Addresses::default(),
+        }
+    }
+}
+
+impl Osc {
+    /// The device timeout as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn device_timeout(&self) -> Result<Duration> {
+        seconds("device timeout", self.device_timeout)
+    }
+
+    /// The reconnect interval as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn reconnect_interval(&self) -> Result<Duration> {
+        seconds("reconnect interval", self.reconnect_interval)
+    }
+
+    /// The maximum reconnect interval as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn max_reconnect_interval(&self) -> Result<Duration> {
+        seconds("maximum reconnect interval", self.max_reconnect_interval)
+    }
+}
+
+/// The mixer that is controlled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    TotalMix,
+    X32,
+    XAir,
+    Reaper,
+    Ardour,
+}
+
+impl Backend {
+    /// The name of the mixer as shown in the heading.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::TotalMix => "TotalMix",
+            Self::X32 => "X32",
+            Self::XAir => "X Air",
+            Self::Reaper => "Reaper",
+            Self::Ardour => "Ardour",
         }
     }
 }
 
+/// What the mute key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MuteKey {
+    #[default]
+    Dim,
+    Mute,
+    DimTapMuteHold,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Volume {
     pub increment: f32,
     pub fine_increment: f32,
     pub max_volume: f32,
+    pub increment_db: Option<f32>,
+    pub fine_increment_db: Option<f32>,
+    pub max_volume_db: Option<f32>,
+    pub min_volume_db: Option<f32>,
+    pub db_precision: usize,
+    pub db_negative_infinity: String,
+    pub ramp_duration: f64,
+    pub ramp_curve: Curve,
+    pub mute_key: MuteKey,
+    pub mute_fade: f64,
+    pub mute_hold_time: f64,
+    pub reference_volume_db: f32,
 }
 
 impl Default for Volume {
@@ -54,10 +200,84 @@ impl Default for Volume {
             increment: 0.02,
             fine_increment: 0.01,
             max_volume: 1.0,
+            increment_db: None,
+            fine_increment_db: None,
+            max_volume_db: None,
+            min_volume_db: None,
+            db_precision: 1,
+            db_negative_infinity: "-oo".to_string(),
+            ramp_duration: 0.0,
+            ramp_curve: Curve::default(),
+            mute_key: MuteKey::default(),
+            mute_fade: 0.0,
+            mute_hold_time: 1.0,
+            reference_volume_db: -18.0,
+        }
+    }
+}
+
+impl Volume {
+    /// The ramp duration as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn ramp_duration(&self) -> Result<Duration> {
+        seconds("ramp duration", self.ramp_duration)
+    }
+
+    /// The mute fade as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn mute_fade(&self) -> Result<Duration> {
+        seconds("mute fade", self.mute_fade)
+    }
+
+    /// The time that the mute key must be held for as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn mute_hold_time(&self) -> Result<Duration> {
+        seconds("mute hold time", self.mute_hold_time)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Acceleration {
+    pub enabled: bool,
+    pub curve: AccelerationCurve,
+    pub rate: f32,
+    pub max_multiplier: f32,
+    pub reset_after: f64,
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            curve: AccelerationCurve::default(),
+            rate: 0.15,
+            max_multiplier: 5.0,
+            reset_after: 0.6,
         }
     }
 }
 
+impl Acceleration {
+    /// The time after which the acceleration resets as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn reset_after(&self) -> Result<Duration> {
+        seconds("acceleration reset time", self.reset_after)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Theme {
@@ -69,13 +289,25 @@ pub struct Theme {
     pub heading_font_size: f32,
     pub volume_readout_color_normal: HexColor,
     pub volume_readout_color_dimmed: HexColor,
+    pub volume_readout_color_disconnected: HexColor,
+    pub volume_readout_color_muted: HexColor,
     pub volume_readout_font_size: f32,
+    pub volume_readout_disconnected_font_size: f32,
     pub volume_bar_height: f32,
     pub volume_bar_top_margin: f32,
     pub volume_bar_horizontal_margin: f32,
     pub volume_bar_background_color: HexColor,
     pub volume_bar_foreground_color_normal: HexColor,
     pub volume_bar_foreground_color_dimmed: HexColor,
+    pub volume_bar_foreground_color_disconnected: HexColor,
+    pub indicator_color: HexColor,
+    pub indicator_font_size: f32,
+    pub meter_height: f32,
+    pub meter_spacing: f32,
+    pub meter_background_color: HexColor,
+    pub meter_rms_color: HexColor,
+    pub meter_peak_color: HexColor,
+    pub meter_peak_hold_color: HexColor,
 }
 
 impl Default for Theme {
@@ -89,13 +321,25 @@ impl Default for Theme {
             heading_font_size: 20.0,
             volume_readout_color_normal: HexColor::WHITE,
             volume_readout_color_dimmed: HexColor::from_u32(0xffa500), // Orange
+            volume_readout_color_disconnected: HexColor::from_u32(0x808080),
+            volume_readout_color_muted: HexColor::from_u32(0xe06464),
             volume_readout_font_size: 40.0,
+            volume_readout_disconnected_font_size: 20.0,
             volume_bar_height: 10.0,
             volume_bar_top_margin: 7.0,
             volume_bar_horizontal_margin: 26.0,
             volume_bar_background_color: HexColor::from_u32(0x333333),
             volume_bar_foreground_color_normal: HexColor::from_u32(0x999999),
             volume_bar_foreground_color_dimmed: HexColor::from_u32(0x996500),
+            volume_bar_foreground_color_disconnected: HexColor::from_u32(0x555555),
+            indicator_color: HexColor::from_u32(0x64a0e0),
+            indicator_font_size: 11.0,
+            meter_height: 3.0,
+            meter_spacing: 2.0,
+            meter_background_color: HexColor::from_u32(0x333333),
+            meter_rms_color: HexColor::from_u32(0x4caf50),
+            meter_peak_color: HexColor::from_u32(0x2e6b30),
+            meter_peak_hold_color: HexColor::from_u32(0xe06464),
         }
     }
 }
@@ -107,6 +351,10 @@ pub struct Interface {
     pub position_offset: f64,
     pub hide_delay: f64,
     pub fade_out_time: f32,
+    pub show_on_external_change: bool,
+    pub heading: String,
+    pub show_meters: bool,
+    pub meter_peak_hold: f64,
 }
 
 impl Default for Interface {
@@ -116,6 +364,72 @@ impl Default for Interface {
             position_offset: 40.0,
             hide_delay: 2.0,
             fade_out_time: 1.0,
+            show_on_external_change: true,
+            heading: "{device} {channel}".to_string(),
+            show_meters: false,
+            meter_peak_hold: 1.5,
+        }
+    }
+}
+
+impl Interface {
+    /// The meter peak hold time as a duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the setting isn't a finite number of seconds no less than 0.
+    pub fn meter_peak_hold(&self) -> Result<Duration> {
+        seconds("meter peak hold", self.meter_peak_hold)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Tray {
+    pub volume_levels_db: Vec<f32>,
+    pub sleep_timer_minutes: Vec<f32>,
+}
+
+impl Default for Tray {
+    fn default() -> Self {
+        Self {
+            volume_levels_db: vec![-40.0, -30.0, -20.0, -10.0, 0.0],
+            sleep_timer_minutes: vec![15.0, 30.0, 60.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier {
+    Alt,
+    Ctrl,
+    Shift,
+    Win,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HotKeyBinding {
+    pub key: String,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NamedChannel {
+    pub name: String,
+    pub bus: Bus,
+    pub number: usize,
+}
+
+impl NamedChannel {
+    #[must_use]
+    pub const fn id(&self) -> ChannelId {
+        ChannelId {
+            bus: self.bus,
+            number: self.number,
         }
     }
 }
@@ -125,6 +439,50 @@ impl Default for Interface {
 pub struct Config {
     pub osc: Osc,
     pub volume: Volume,
+    pub acceleration: Acceleration,
     pub theme: Theme,
     pub interface: Interface,
+    pub tray: Tray,
+    pub channels: Vec<NamedChannel>,
+    pub presets: Vec<Preset>,
+    pub hotkeys: Vec<HotKeyBinding>,
+}
+
+impl Config {
+    /// What the mute key does with the mixer that is controlled, where the mute key always mutes
+    /// mixers other than `TotalMix` since they have no dim button.
+    #[must_use]
+    pub fn mute_key(&self) -> MuteKey {
+        if self.osc.backend == Backend::TotalMix {
+            self.volume.mute_key
+        } else {
+            MuteKey::Mute
+        }
+    }
+
+    /// Checks the settings that can't be rejected while deserializing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first setting that is invalid.
+    pub fn validate(&self) -> Result<()> {
+        self.osc.device_timeout()?;
+        self.osc.reconnect_interval()?;
+        self.osc.max_reconnect_interval()?;
+        self.osc.addresses.validate()?;
+        self.volume.ramp_duration()?;
+        self.volume.mute_fade()?;
+        self.volume.mute_hold_time()?;
+        self.acceleration.reset_after()?;
+        self.interface.meter_peak_hold()?;
+        for &minutes in &self.tray.sleep_timer_minutes {
+            actions::fade_duration(minutes)?;
+        }
+        for binding in &self.hotkeys {
+            if let Action::FadeOut { minutes } = binding.action {
+                actions::fade_duration(minutes)?;
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,252 @@
+use rosc::{OscMessage, OscType};
+use serde::Deserialize;
+
+use crate::{
+    addresses::Control,
+    backend::{number, MixerBackend},
+};
+
+const MUTE_OSC_ADDR: &str = "/master/mute";
+
+// The normalised fader position follows a logarithmic law like that of `TotalMix`, with 0 dB at 75%
+// of the fader's travel and the most gain that the master fader of the DAW provides at the top.
+const UNITY_VOLUME: f32 = 0.75;
+const MIN_DB: f32 = -90.0;
+
+/// The DAWs whose OSC control surfaces provide the master bus, which differ in their addresses
+/// and in how feedback is subscribed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Host {
+    Reaper,
+    Ardour,
+}
+
+impl Host {
+    const fn db_osc_addr(self) -> &'static str {
+        match self {
+            Self::Reaper => "/master/volume/db",
+            Self::Ardour => "/master/gain",
+        }
+    }
+
+    const fn fader_osc_addr(self) -> &'static str {
+        match self {
+            Self::Reaper => "/master/volume",
+            Self::Ardour => "/master/fader",
+        }
+    }
+
+    const fn max_db(self) -> f32 {
+        match self {
+            Self::Reaper => 12.0,
+            Self::Ardour => 6.0,
+        }
+    }
+}
+
+/// The master bus of a DAW.
+///
+/// The volume is exchanged in decibels so that levels are exact regardless of the fader law of the
+/// DAW, and the normalised fader position of the DAW is only used for silence.
+#[derive(Debug)]
+pub struct Daw {
+    host: Host,
+}
+
+impl Daw {
+    #[must_use]
+    pub const fn new(host: Host) -> Self {
+        Self { host }
+    }
+}
+
+impl MixerBackend for Daw {
+    fn request(&self) -> Vec<OscMessage> {
+        let message = match self.host {
+            // Control surface: Refresh all surfaces
+            Host::Reaper => message("/action/41743", None),
+            // Button status (1), variable control values (2) and the master section (16).
+            Host::Ardour => message("/set_surface/feedback", Some(OscType::Int(19))),
+        };
+        vec![message]
+    }
+
+    fn decode(&self, message: &OscMessage) -> Option<(Control, OscType)> {
+        let addr = message.addr.as_str();
+        let value = number(message.args.first()?)?;
+        if addr == self.host.db_osc_addr() {
+            Some((Control::Volume, OscType::Float(self.db_to_volume(value))))
+        } else if addr == self.host.fader_osc_addr() && value <= 0.0 {
+            Some((Control::Volume, OscType::Float(0.0)))
+        } else if addr == MUTE_OSC_ADDR {
+            Some((
+                Control::Mute,
+                OscType::Float(if value >= 0.5 { 1.0 } else { 0.0 }),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self, control: Control, value: f32) -> Option<OscMessage> {
+        match control {
+            Control::Volume if value <= 0.0 => Some(message(
+                self.host.fader_osc_addr(),
+                Some(OscType::Float(0.0)),
+            )),
+            Control::Volume => Some(message(
+                self.host.db_osc_addr(),
+                Some(OscType::Float(self.volume_to_db(value))),
+            )),
+            Control::Mute => {
+                let muted = value >= 0.5;
+                let arg = match self.host {
+                    Host::Reaper => OscType::Float(if muted { 1.0 } else { 0.0 }),
+                    Host::Ardour => OscType::Int(i32::from(muted)),
+                };
+                Some(message(MUTE_OSC_ADDR, Some(arg)))
+            }
+            _ => None,
+        }
+    }
+
+    // Reaper sends feedback to the device port configured for the control surface instead.
+    fn replies_to_sender(&self) -> bool {
+        self.host == Host::Ardour
+    }
+
+    fn volume_to_db(&self, volume: f32) -> f32 {
+        if volume <= 0.0 {
+            return f32::NEG_INFINITY;
+        }
+        self.host.max_db() * (volume.min(1.0) / UNITY_VOLUME).ln() / UNITY_VOLUME.recip().ln()
+    }
+
+    fn db_to_volume(&self, db: f32) -> f32 {
+        if db == f32::NEG_INFINITY {
+            return 0.0;
+        }
+        (UNITY_VOLUME * (db * UNITY_VOLUME.recip().ln() / self.host.max_db()).exp()).clamp(0.0, 1.0)
+    }
+
+    fn min_db(&self) -> f32 {
+        MIN_DB
+    }
+}
+
+fn message(addr: &str, arg: Option<OscType>) -> OscMessage {
+    OscMessage {
+        addr: addr.to_string(),
+        args: arg.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buttons::Button,
+        comms::{UdpReceiver, UdpSender},
+        manager::Manager,
+        testing::{assert_close, bind_desk, localhost, receive, reply},
+    };
+
+    #[test]
+    fn fader_law() {
+        let reaper = Daw::new(Host::Reaper);
+        assert_close(reaper.volume_to_db(UNITY_VOLUME), 0.0);
+        assert_close(reaper.volume_to_db(1.0), 12.0);
+        assert_close(Daw::new(Host::Ardour).volume_to_db(1.0), 6.0);
+        assert!(reaper.volume_to_db(0.0).is_infinite());
+        for db in [MIN_DB, -40.0, -6.0, 0.0, 10.0, 12.0] {
+            assert_close(reaper.volume_to_db(reaper.db_to_volume(db)), db);
+        }
+        assert_close(reaper.db_to_volume(20.0), 1.0);
+    }
+
+    #[test]
+    fn decode() {
+        let backend = Daw::new(Host::Ardour);
+        let (control, volume) = backend
+            .decode(&message("/master/gain", Some(OscType::Float(-20.0))))
+            .unwrap();
+        assert_eq!(control, Control::Volume);
+        assert_eq!(volume, OscType::Float(backend.db_to_volume(-20.0)));
+        assert_eq!(
+            backend.decode(&message("/master/fader", Some(OscType::Float(0.0)))),
+            Some((Control::Volume, OscType::Float(0.0)))
+        );
+        assert_eq!(
+            backend.decode(&message("/master/fader", Some(OscType::Float(0.5)))),
+            None
+        );
+        assert_eq!(
+            backend.decode(&message("/master/mute", Some(OscType::Int(1)))),
+            Some((Control::Mute, OscType::Float(1.0)))
+        );
+        assert_eq!(
+            backend.decode(&message("/master/volume/db", Some(OscType::Float(-20.0)))),
+            None
+        );
+    }
+
+    #[test]
+    fn encode() {
+        let backend = Daw::new(Host::Reaper);
+        let volume = backend.encode(Control::Volume, backend.db_to_volume(-20.0));
+        match volume.unwrap().args.first() {
+            Some(OscType::Float(db)) => assert_close(*db, -20.0),
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            backend.encode(Control::Volume, 0.0),
+            Some(message("/master/volume", Some(OscType::Float(0.0))))
+        );
+        assert_eq!(
+            backend.encode(Control::Mute, 1.0),
+            Some(message("/master/mute", Some(OscType::Float(1.0))))
+        );
+        assert!(!backend.supports(Control::Button(Button::Dim)));
+        assert!(!backend.replies_to_sender());
+        assert!(Daw::new(Host::Ardour).replies_to_sender());
+    }
+
+    #[test]
+    fn manager() {
+        let (desk, desk_addr) = bind_desk();
+        let receiver = UdpReceiver::bind(localhost()).unwrap();
+        let receiver_addr = receiver.local_addr().unwrap();
+        let mut manager: Manager<UdpSender, UdpReceiver> = Manager::default();
+        manager.set_backend(Box::new(Daw::new(Host::Reaper)));
+        manager.set_sender(UdpSender::new(desk_addr).unwrap());
+        manager.set_receiver(receiver);
+
+        // Levels above the gain of `TotalMix` are read as the DAW reports them.
+        manager.request_volume().unwrap();
+        assert_eq!(receive(&desk).0.addr, "/action/41743");
+        reply(
+            &desk,
+            receiver_addr,
+            message("/master/volume/db", Some(OscType::Float(10.0))),
+        );
+        reply(
+            &desk,
+            receiver_addr,
+            message("/master/mute", Some(OscType::Float(0.0))),
+        );
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.initialized());
+        assert_eq!(manager.volume_db().unwrap(), "10.0 dB");
+        assert!(!manager.muted());
+
+        assert!(manager.set_volume_db(-20.0).unwrap());
+        let (volume, _) = receive(&desk);
+        assert_eq!(volume.addr, "/master/volume/db");
+        match volume.args.first() {
+            Some(OscType::Float(db)) => assert_close(*db, -20.0),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// An approximation of the law used by the master fader in TotalMix FX, where the fader position is
+// normalised between 0.0 and 1.0, 0 dB sits at roughly 82% of the fader's travel and the top of the
+// fader provides 6 dB of gain.  The level changes logarithmically with the fader position and drops
+// to -oo at the bottom of the fader.
+const UNITY_VOLUME: f32 = 0.817;
+const MAX_DB: f32 = 6.0;
+
+/// The lowest level that may be reached by stepping the volume down, below which the volume is
+/// considered to be -oo.
+pub const MIN_DB: f32 = -65.0;
+
+fn db_per_decade() -> f32 {
+    MAX_DB / UNITY_VOLUME.recip().log10()
+}
+
+/// Converts a normalised fader position into decibels, returning negative infinity at the bottom
+/// of the fader.
+#[must_use]
+pub fn volume_to_db(volume: f32) -> f32 {
+    if volume <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    db_per_decade() * (volume.min(1.0) / UNITY_VOLUME).log10()
+}
+
+/// Converts decibels into a normalised fader position, clamped to the travel of the fader.
+#[must_use]
+pub fn db_to_volume(db: f32) -> f32 {
+    if db == f32::NEG_INFINITY {
+        return 0.0;
+    }
+    (UNITY_VOLUME * 10f32.powf(db / db_per_decade())).clamp(0.0, 1.0)
+}
+
+/// Formats a normalised fader position as a decibel readout (e.g. "-12.5 dB") with the given
+/// number of decimal places, using the given text when the volume is -oo.
+#[must_use]
+pub fn format_db(volume: f32, precision: usize, negative_infinity: &str) -> String {
+    format_decibels(volume_to_db(volume), precision, negative_infinity)
+}
+
+/// Formats a level in decibels as a readout in the same way as [`format_db`].
+#[must_use]
+pub fn format_decibels(db: f32, precision: usize, negative_infinity: &str) -> String {
+    if db == f32::NEG_INFINITY {
+        return negative_infinity.to_string();
+    }
+    format!("{db:.precision$} dB")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::TOTALMIX_READOUTS, testing::assert_close};
+
+    fn parse_readout(readout: &str) -> f32 {
+        if readout == "-oo" {
+            return f32::NEG_INFINITY;
+        }
+        readout.trim_end_matches(" dB").parse().unwrap()
+    }
+
+    // The law must reproduce the readouts of TotalMix FX to the precision that it displays them.
+    #[test]
+    fn matches_totalmix_readouts() {
+        for (position, readout) in TOTALMIX_READOUTS {
+            let db = parse_readout(readout);
+            assert_eq!(format_db(position, 1, "-oo"), readout);
+            assert!((db_to_volume(db) - position).abs() < 0.001, "{readout}");
+        }
+    }
+
+    #[test]
+    fn volume_to_db_bounds() {
+        assert!(volume_to_db(0.0).is_infinite() && volume_to_db(0.0) < 0.0);
+        assert_close(volume_to_db(UNITY_VOLUME), 0.0);
+        assert_close(volume_to_db(1.0), 6.0);
+    }
+
+    #[test]
+    fn db_to_volume_bounds() {
+        assert_close(db_to_volume(f32::NEG_INFINITY), 0.0);
+        assert_close(db_to_volume(0.0), UNITY_VOLUME);
+        assert_close(db_to_volume(6.0), 1.0);
+        assert_close(db_to_volume(12.0), 1.0);
+    }
+
+    #[test]
+    fn format_db_precision() {
+        assert_eq!(format_db(0.5, 1, "-oo"), "-14.6 dB");
+        assert_eq!(format_db(0.5, 2, "-oo"), "-14.58 dB");
+        assert_eq!(format_db(1.0, 0, "-oo"), "6 dB");
+    }
+
+    #[test]
+    fn format_db_negative_infinity() {
+        assert_eq!(format_db(0.0, 1, "-oo"), "-oo");
+        assert_eq!(format_db(0.0, 1, "-inf dB"), "-inf dB");
+    }
+
+    #[test]
+    fn round_trip() {
+        for db in [-60.0, -35.5, -18.0, -10.0, -0.5, 3.0] {
+            assert_close(volume_to_db(db_to_volume(db)), db);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+// Global hotkeys don't report when a key is released, so a key is considered released once its
+// auto-repeat stops for longer than the keyboard repeat delay.
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Tap,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    first: Instant,
+    last: Instant,
+    held: bool,
+}
+
+/// Distinguishes between a key being tapped and a key being held down using the auto-repeat of
+/// the key.
+#[derive(Debug)]
+pub struct TapOrHold {
+    hold_time: Duration,
+    press: Option<Press>,
+}
+
+impl TapOrHold {
+    #[must_use]
+    pub const fn new(hold_time: Duration) -> Self {
+        Self {
+            hold_time,
+            press: None,
+        }
+    }
+
+    /// Records a press (or auto-repeat) of the key, returning a hold once the key has been held
+    /// down for long enough.
+    pub fn press(&mut self, now: Instant) -> Option<Gesture> {
+        let released = self.poll(now);
+        let press = self.press.get_or_insert(Press {
+            first: now,
+            last: now,
+            held: false,
+        });
+        press.last = now;
+
+        if !press.held && now.duration_since(press.first) >= self.hold_time {
+            press.held = true;
+            return Some(Gesture::Hold);
+        }
+        released
+    }
+
+    /// Checks whether the key has since been released, returning a tap if it was released before
+    /// it was held down for long enough.
+    pub fn poll(&mut self, now: Instant) -> Option<Gesture> {
+        let press = self.press?;
+        if now.duration_since(press.last) < RELEASE_TIMEOUT {
+            return None;
+        }
+        self.press = None;
+        (!press.held).then_some(Gesture::Tap)
+    }
+
+    /// The time at which the key will be considered released if it isn't pressed again.
+    #[must_use]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.press.map(|press| press.last + RELEASE_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tap() {
+        let mut gesture = TapOrHold::new(Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(gesture.press(start), None);
+        assert_eq!(gesture.poll(start + Duration::from_millis(100)), None);
+        assert_eq!(gesture.deadline(), Some(start + RELEASE_TIMEOUT));
+        assert_eq!(gesture.poll(start + RELEASE_TIMEOUT), Some(Gesture::Tap));
+        assert_eq!(gesture.deadline(), None);
+    }
+
+    #[test]
+    fn hold() {
+        let mut gesture = TapOrHold::new(Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(gesture.press(start), None);
+        let repeats: Vec<_> = (1..=40)
+            .map(|index| gesture.press(start + Duration::from_millis(500 + 30 * index)))
+            .collect();
+        assert_eq!(
+            repeats
+                .iter()
+                .filter(|repeat| **repeat == Some(Gesture::Hold))
+                .count(),
+            1
+        );
+        assert_eq!(gesture.poll(start + Duration::from_secs(3)), None);
+        assert_eq!(gesture.deadline(), None);
+    }
+
+    #[test]
+    fn tap_detected_on_next_press() {
+        let mut gesture = TapOrHold::new(Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(gesture.press(start), None);
+        assert_eq!(
+            gesture.press(start + Duration::from_secs(2)),
+            Some(Gesture::Tap)
+        );
+        assert!(gesture.deadline().is_some());
+    }
+}
@@ -1,42 +1,112 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
 
+use anyhow::Result;
 use egui::{
-    style::DebugOptions, text::LayoutJob, vec2, Align, CentralPanel, Context, Direction, FontData,
-    FontDefinitions, FontFamily, FontId, Frame, Id, Layout, Rect, RichText, Rounding, Sense, Style,
-    TextFormat, Ui, Vec2,
+    pos2, style::DebugOptions, text::LayoutJob, vec2, Align, CentralPanel, Context, Direction,
+    FontData, FontDefinitions, FontFamily, FontId, Frame, Id, Layout, Rect, RichText, Rounding,
+    Sense, Style, TextFormat, Ui, Vec2,
 };
 
 use crate::{
+    buttons::Button,
+    channels::ChannelId,
     colors::ToColor32,
-    comms::{UdpReceiver, UdpSender},
     config::Config,
-    manager::Manager,
+    manager::{ConnectionState, ReferenceSide, StateChange},
+    meters::{Levels, PeakHold},
 };
 
+const MAIN_CHANNEL_NAME: &str = "Volume";
+
+/// A part of the heading template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeadingPart<'a> {
+    Text(&'a str),
+    Device,
+    Channel,
+}
+
+fn parse_heading(template: &str) -> Vec<HeadingPart<'_>> {
+    let placeholders = [
+        ("{device}", HeadingPart::Device),
+        ("{channel}", HeadingPart::Channel),
+    ];
+    let next_placeholder = |rest: &str| {
+        placeholders
+            .iter()
+            .filter_map(|(placeholder, part)| {
+                rest.find(placeholder)
+                    .map(|index| (index, placeholder.len(), *part))
+            })
+            .min_by_key(|(index, _, _)| *index)
+    };
+
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some((index, length, part)) = next_placeholder(rest) {
+        if index > 0 {
+            parts.push(HeadingPart::Text(&rest[..index]));
+        }
+        parts.push(part);
+        rest = &rest[index + length..];
+    }
+    if !rest.is_empty() {
+        parts.push(HeadingPart::Text(rest));
+    }
+    parts
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Appearance {
+    Normal,
+    Dimmed,
+    Muted,
+    Disconnected,
+}
+
 pub struct VolumeControlApp {
-    manager: Arc<Manager<UdpSender, UdpReceiver>>,
     config: Arc<Config>,
     id: Id,
     show_time: Option<f64>,
+    volume: f32,
+    volume_db: Option<String>,
+    active_buttons: HashSet<Button>,
+    heading_channel: Option<ChannelId>,
+    recalled_name: Option<String>,
+    reference_side: Option<ReferenceSide>,
+    channel_names: HashMap<ChannelId, String>,
+    levels: Levels,
+    peak_holds: [PeakHold; 2],
+    held_peaks: [f32; 2],
+    muted: bool,
+    connection_state: ConnectionState,
 }
 
 impl VolumeControlApp {
-    pub fn new(
-        egui_ctx: &Context,
-        manager: Arc<Manager<UdpSender, UdpReceiver>>,
-        config: Arc<Config>,
-    ) -> Self {
-        // Set the default font.
+    /// Creates the window contents, loading the fonts and style that it's drawn with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the meter peak hold time in the configuration is invalid.
+    pub fn new(egui_ctx: &Context, config: Arc<Config>) -> Result<Self> {
+        // Set the default font (falling back to the egui font when Segoe UI isn't available).
         let mut fonts = FontDefinitions::default();
-        fonts.font_data.insert(
-            "Segoe UI".to_string(),
-            FontData::from_static(include_bytes!(r"C:\Windows\Fonts\segoeui.ttf")),
-        );
-        fonts
-            .families
-            .entry(FontFamily::Proportional)
-            .or_default()
-            .insert(0, "Segoe UI".to_string());
+        if let Some(font) = load_segoe_ui() {
+            fonts
+                .font_data
+                .insert("Segoe UI".to_string(), FontData::from_owned(font));
+            fonts
+                .families
+                .entry(FontFamily::Proportional)
+                .or_default()
+                .insert(0, "Segoe UI".to_string());
+        }
         egui_ctx.set_fonts(fonts);
 
         let mut style = Style::default();
@@ -51,14 +121,94 @@ impl VolumeControlApp {
         }
         egui_ctx.set_style(style);
 
-        Self {
-            manager,
+        let peak_hold = PeakHold::new(config.interface.meter_peak_hold()?);
+        Ok(Self {
             config,
             id: Id::new("app"),
             show_time: None,
+            volume: 0.0,
+            volume_db: None,
+            active_buttons: HashSet::new(),
+            heading_channel: None,
+            recalled_name: None,
+            reference_side: None,
+            channel_names: HashMap::new(),
+            levels: Levels::default(),
+            peak_holds: [peak_hold; 2],
+            held_peaks: [0.0; 2],
+            muted: false,
+            connection_state: ConnectionState::Disconnected,
+        })
+    }
+
+    pub fn apply_change(&mut self, change: &StateChange) {
+        // A recalled preset or snapshot is named while the recall is shown, which includes the
+        // changes that it makes, but not once the state changes after that.
+        if self.show_time.is_none()
+            && matches!(
+                change,
+                StateChange::Volume(..)
+                    | StateChange::Button(..)
+                    | StateChange::Muted(_)
+                    | StateChange::Reference(_)
+            )
+        {
+            self.recalled_name = None;
+        }
+
+        match change {
+            StateChange::Volume(volume, volume_db) => {
+                self.volume = *volume;
+                self.volume_db.clone_from(volume_db);
+            }
+            StateChange::VolumeDb(volume_db) => self.volume_db.clone_from(volume_db),
+            StateChange::Button(button, active) => {
+                if *active {
+                    self.active_buttons.insert(*button);
+                } else {
+                    self.active_buttons.remove(button);
+                }
+            }
+            StateChange::Muted(muted) => self.muted = *muted,
+            StateChange::PresetRecalled(name) => self.recalled_name = Some(name.clone()),
+            StateChange::SnapshotRecalled(snapshot, name) => {
+                self.recalled_name = Some(
+                    name.clone()
+                        .unwrap_or_else(|| format!("Snapshot {snapshot}")),
+                );
+            }
+            StateChange::Reference(side) => self.reference_side = Some(*side),
+            StateChange::Levels(levels) => {
+                let now = Instant::now();
+                self.levels = *levels;
+                self.held_peaks = [
+                    self.peak_holds[0].update(levels.left.peak, now),
+                    self.peak_holds[1].update(levels.right.peak, now),
+                ];
+            }
+            StateChange::Channel(channel_id, channel) => {
+                if let Some(name) = &channel.name {
+                    self.channel_names.insert(*channel_id, name.clone());
+                }
+            }
+            StateChange::Connection(connection_state) => self.connection_state = *connection_state,
+            StateChange::SnapshotNamed(..) => (),
         }
     }
 
+    /// Whether the window is being shown, which doesn't include fading it out.
+    #[must_use]
+    pub const fn shown(&self) -> bool {
+        self.show_time.is_some()
+    }
+
+    /// Sets the channel named in the heading, where None names the main volume.  This also stops
+    /// naming any preset or snapshot that was recalled, until another one is recalled.
+    pub fn set_heading_channel(&mut self, channel: Option<ChannelId>) {
+        self.heading_channel = channel;
+        self.recalled_name = None;
+    }
+
     pub fn draw(&mut self, egui_ctx: &Context, restart: bool) {
         let opacity = {
             // A global hotkey has been pressed so display the UI.
@@ -94,9 +244,26 @@ impl VolumeControlApp {
                 ..Default::default()
             })
             .show(egui_ctx, |ui| {
-                let volume_db = self.manager.volume_db().unwrap_or_else(|| "-".to_string());
-                let volume = self.manager.volume();
-                let dimmed = self.manager.dimmed();
+                let connected = matches!(
+                    self.connection_state,
+                    ConnectionState::Connected | ConnectionState::Stale { .. }
+                );
+                let volume_db = if !connected {
+                    None
+                } else if self.muted {
+                    Some("Muted".to_string())
+                } else {
+                    self.volume_db.clone()
+                };
+                let volume = if connected { self.volume.max(0.0) } else { 0.0 };
+                let appearance = match self.connection_state {
+                    ConnectionState::Connected if self.muted => Appearance::Muted,
+                    ConnectionState::Connected if self.active_buttons.contains(&Button::Dim) => {
+                        Appearance::Dimmed
+                    }
+                    ConnectionState::Connected => Appearance::Normal,
+                    _ => Appearance::Disconnected,
+                };
                 let scaling = self.config.interface.scaling;
 
                 // Draw the TotalMix Volume heading.
@@ -120,7 +287,13 @@ impl VolumeControlApp {
                     ),
                     Layout::centered_and_justified(Direction::TopDown),
                     |ui| {
-                        self.draw_volume_readout(ui, opacity, scaling, &volume_db, dimmed);
+                        self.draw_volume_readout(
+                            ui,
+                            opacity,
+                            scaling,
+                            volume_db.as_deref(),
+                            appearance,
+                        );
                     },
                 );
 
@@ -132,40 +305,50 @@ impl VolumeControlApp {
                     ),
                     Layout::centered_and_justified(Direction::TopDown).with_main_align(Align::Min),
                     |ui| {
-                        self.draw_volume_bar(ui, opacity, scaling, volume, dimmed);
+                        self.draw_volume_bar(ui, opacity, scaling, volume, appearance);
+                        if connected {
+                            if self.config.interface.show_meters {
+                                self.draw_meters(ui, opacity, scaling);
+                            }
+                            self.draw_indicators(ui, opacity, scaling);
+                        }
                     },
                 );
             });
     }
 
     fn draw_heading(&self, ui: &mut Ui, opacity: f32, scaling: f32) {
+        // The channel is named as it is in TotalMix once its name has been received, unless a
+        // preset or snapshot was just recalled in which case it is named instead.
+        let channel_name = self
+            .recalled_name
+            .as_ref()
+            .or_else(|| {
+                self.heading_channel
+                    .and_then(|channel| self.channel_names.get(&channel))
+            })
+            .map_or(MAIN_CHANNEL_NAME, String::as_str);
+
         let mut job = LayoutJob::default();
-        job.append(
-            "TotalMix ",
-            0.0,
-            TextFormat {
-                font_id: FontId::proportional(self.config.theme.heading_font_size * scaling),
-                color: self
-                    .config
-                    .theme
-                    .heading_totalmix_color
-                    .to_colour32_scaled(opacity),
-                ..Default::default()
-            },
-        );
-        job.append(
-            "Volume",
-            0.0,
-            TextFormat {
-                font_id: FontId::proportional(self.config.theme.heading_font_size * scaling),
-                color: self
-                    .config
-                    .theme
-                    .heading_volume_color
-                    .to_colour32_scaled(opacity),
-                ..Default::default()
-            },
-        );
+        for part in parse_heading(&self.config.interface.heading) {
+            let (text, color) = match part {
+                HeadingPart::Text(text) => (text, self.config.theme.heading_totalmix_color),
+                HeadingPart::Device => (
+                    self.config.osc.backend.name(),
+                    self.config.theme.heading_totalmix_color,
+                ),
+                HeadingPart::Channel => (channel_name, self.config.theme.heading_volume_color),
+            };
+            job.append(
+                text,
+                0.0,
+                TextFormat {
+                    font_id: FontId::proportional(self.config.theme.heading_font_size * scaling),
+                    color: color.to_colour32_scaled(opacity),
+                    ..Default::default()
+                },
+            );
+        }
         ui.label(job);
     }
 
@@ -174,22 +357,39 @@ impl VolumeControlApp {
         ui: &mut Ui,
         opacity: f32,
         scaling: f32,
-        volume_db: &str,
-        dimmed: bool,
+        volume_db: Option<&str>,
+        appearance: Appearance,
     ) {
-        let volume_readout_color = if dimmed {
-            self.config.theme.volume_readout_color_dimmed
-        } else {
-            self.config.theme.volume_readout_color_normal
+        let volume_readout_color = match appearance {
+            Appearance::Normal => self.config.theme.volume_readout_color_normal,
+            Appearance::Dimmed => self.config.theme.volume_readout_color_dimmed,
+            Appearance::Muted => self.config.theme.volume_readout_color_muted,
+            Appearance::Disconnected => self.config.theme.volume_readout_color_disconnected,
         };
+
+        // Without a connection to the device, there's no volume to display.
+        let (text, font_size) = volume_db.map_or(
+            (
+                "No Connection",
+                self.config.theme.volume_readout_disconnected_font_size,
+            ),
+            |volume_db| (volume_db, self.config.theme.volume_readout_font_size),
+        );
         ui.label(
-            RichText::new(volume_db)
-                .size(self.config.theme.volume_readout_font_size * scaling)
+            RichText::new(text)
+                .size(font_size * scaling)
                 .color(volume_readout_color.to_colour32_scaled(opacity)),
         );
     }
 
-    fn draw_volume_bar(&self, ui: &mut Ui, opacity: f32, scaling: f32, volume: f32, dimmed: bool) {
+    fn draw_volume_bar(
+        &self,
+        ui: &mut Ui,
+        opacity: f32,
+        scaling: f32,
+        volume: f32,
+        appearance: Appearance,
+    ) {
         // Add a little top padding to align with the text above which has a little
         // padding due to the font used.
         ui.add_space(self.config.theme.volume_bar_top_margin * scaling);
@@ -220,10 +420,12 @@ impl VolumeControlApp {
                 volume_bar_background.height(),
             ),
         );
-        let volume_bar_foreground_color = if dimmed {
-            self.config.theme.volume_bar_foreground_color_dimmed
-        } else {
-            self.config.theme.volume_bar_foreground_color_normal
+        let volume_bar_foreground_color = match appearance {
+            Appearance::Normal | Appearance::Muted => {
+                self.config.theme.volume_bar_foreground_color_normal
+            }
+            Appearance::Dimmed => self.config.theme.volume_bar_foreground_color_dimmed,
+            Appearance::Disconnected => self.config.theme.volume_bar_foreground_color_disconnected,
         };
         ui.painter().rect_filled(
             volume_bar_foreground,
@@ -231,4 +433,140 @@ impl VolumeControlApp {
             volume_bar_foreground_color.to_colour32_scaled(opacity),
         );
     }
+
+    fn draw_meters(&self, ui: &mut Ui, opacity: f32, scaling: f32) {
+        let theme = &self.config.theme;
+        for (level, held_peak) in [self.levels.left, self.levels.right]
+            .into_iter()
+            .zip(self.held_peaks)
+        {
+            ui.add_space(theme.meter_spacing * scaling);
+            let (meter_background, _response) = ui.allocate_exact_size(
+                vec2(
+                    ui.available_width() - theme.volume_bar_horizontal_margin * 2.0 * scaling,
+                    theme.meter_height * scaling,
+                ),
+                Sense::hover(),
+            );
+            let meter_fill = |value: f32| {
+                Rect::from_min_size(
+                    meter_background.min,
+                    vec2(meter_background.width() * value, meter_background.height()),
+                )
+            };
+
+            let painter = ui.painter();
+            painter.rect_filled(
+                meter_background,
+                Rounding::none(),
+                theme.meter_background_color.to_colour32_scaled(opacity),
+            );
+            painter.rect_filled(
+                meter_fill(level.peak),
+                Rounding::none(),
+                theme.meter_peak_color.to_colour32_scaled(opacity),
+            );
+            painter.rect_filled(
+                meter_fill(level.rms),
+                Rounding::none(),
+                theme.meter_rms_color.to_colour32_scaled(opacity),
+            );
+
+            // Mark the held peak with a thin line that remains inside the meter at either end.
+            if held_peak > 0.0 {
+                let held_peak_x = meter_background
+                    .width()
+                    .mul_add(held_peak, meter_background.left())
+                    .max(meter_background.left() + scaling);
+                painter.rect_filled(
+                    Rect::from_min_max(
+                        pos2(held_peak_x - scaling, meter_background.top()),
+                        pos2(held_peak_x, meter_background.bottom()),
+                    ),
+                    Rounding::none(),
+                    theme.meter_peak_hold_color.to_colour32_scaled(opacity),
+                );
+            }
+        }
+    }
+
+    fn draw_indicators(&self, ui: &mut Ui, opacity: f32, scaling: f32) {
+        // The reference side is only indicated once the reference level has been toggled to, and
+        // dim is already indicated by the colors of the volume read-out and bar.
+        let labels: Vec<_> = self
+            .reference_side
+            .map(|side| match side {
+                ReferenceSide::A => "A: Listening",
+                ReferenceSide::B => "B: Reference",
+            })
+            .into_iter()
+            .chain(
+                Button::ALL
+                    .into_iter()
+                    .filter(|button| *button != Button::Dim && self.active_buttons.contains(button))
+                    .map(Button::label),
+            )
+            .collect();
+        if labels.is_empty() {
+            return;
+        }
+
+        ui.label(
+            RichText::new(labels.join("  \u{b7}  "))
+                .size(self.config.theme.indicator_font_size * scaling)
+                .color(
+                    self.config
+                        .theme
+                        .indicator_color
+                        .to_colour32_scaled(opacity),
+                ),
+        );
+    }
+}
+
+fn load_segoe_ui() -> Option<Vec<u8>> {
+    let windows_dir = env::var_os("WINDIR")?;
+    fs::read(PathBuf::from(windows_dir).join("Fonts").join("segoeui.ttf")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_template() {
+        assert_eq!(
+            parse_heading("{device} {channel}"),
+            vec![
+                HeadingPart::Device,
+                HeadingPart::Text(" "),
+                HeadingPart::Channel
+            ]
+        );
+        assert_eq!(
+            parse_heading("{channel} on {device}!"),
+            vec![
+                HeadingPart::Channel,
+                HeadingPart::Text(" on "),
+                HeadingPart::Device,
+                HeadingPart::Text("!")
+            ]
+        );
+        assert_eq!(parse_heading("Volume"), vec![HeadingPart::Text("Volume")]);
+        assert_eq!(parse_heading(""), vec![]);
+    }
+
+    #[test]
+    fn recalled_name_cleared_by_later_changes() {
+        let mut app =
+            VolumeControlApp::new(&Context::default(), Arc::new(Config::default())).unwrap();
+        app.show_time = Some(0.0);
+        app.apply_change(&StateChange::PresetRecalled("Mix Reference".to_string()));
+        app.apply_change(&StateChange::Volume(0.5, None));
+        assert_eq!(app.recalled_name.as_deref(), Some("Mix Reference"));
+
+        app.show_time = None;
+        app.apply_change(&StateChange::Button(Button::Mono, true));
+        assert_eq!(app.recalled_name, None);
+    }
 }
@@ -2,40 +2,99 @@ use anyhow::Result;
 use thiserror::Error;
 use windows::Win32::UI::{
     Input::KeyboardAndMouse::{
-        RegisterHotKey, MOD_SHIFT, VK_VOLUME_DOWN, VK_VOLUME_MUTE, VK_VOLUME_UP,
+        RegisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN, VIRTUAL_KEY,
+        VK_DELETE, VK_DOWN, VK_END, VK_F1, VK_HOME, VK_INSERT, VK_LEFT, VK_MEDIA_NEXT_TRACK,
+        VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_MEDIA_STOP, VK_NEXT, VK_NUMPAD0, VK_PAUSE,
+        VK_PRIOR, VK_RIGHT, VK_SCROLL, VK_SPACE, VK_UP, VK_VOLUME_DOWN, VK_VOLUME_MUTE,
+        VK_VOLUME_UP,
     },
     WindowsAndMessaging::{MSG, WM_HOTKEY},
 };
 
+use crate::{
+    actions::Action,
+    config::{Config, HotKeyBinding, Modifier, MuteKey},
+};
+
+// Hotkeys configured by the user are identified by their position in the configuration, offset
+// so they never clash with the built-in hotkeys.
+const CUSTOM_HOTKEY_ID_START: usize = 100;
+
 #[derive(Error, Debug)]
 #[error("unable to bind the required hotkey")]
 pub struct HotKeyBindError;
 
-#[derive(Debug)]
+#[derive(Error, Debug)]
+#[error("unknown key '{0}'")]
+pub struct UnknownKeyError(String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HotKey {
-    VolumeUp = 1,
-    VolumeDown = 2,
-    VolumeUpFine = 3,
-    VolumeDownfine = 4,
-    Mute = 5,
+    VolumeUp,
+    VolumeDown,
+    VolumeUpFine,
+    VolumeDownfine,
+    Mute,
+    Custom(usize),
 }
 
-pub fn register() -> Result<()> {
-    for (id, modifiers, key) in [
-        (HotKey::VolumeUp, None, VK_VOLUME_UP),
-        (HotKey::VolumeDown, None, VK_VOLUME_DOWN),
-        (HotKey::VolumeUpFine, Some(MOD_SHIFT), VK_VOLUME_UP),
-        (HotKey::VolumeDownfine, Some(MOD_SHIFT), VK_VOLUME_DOWN),
-        (HotKey::Mute, None, VK_VOLUME_MUTE),
-    ] {
-        let result = unsafe {
-            RegisterHotKey(
-                None,
-                id as i32,
-                modifiers.unwrap_or_default(),
-                u32::from(key.0),
-            )
-        };
+impl HotKey {
+    const fn id(self) -> usize {
+        match self {
+            Self::VolumeUp => 1,
+            Self::VolumeDown => 2,
+            Self::VolumeUpFine => 3,
+            Self::VolumeDownfine => 4,
+            Self::Mute => 5,
+            Self::Custom(index) => CUSTOM_HOTKEY_ID_START + index,
+        }
+    }
+
+    /// Determines the action that the hotkey performs according to the configuration, which is
+    /// unknown for the mute key when it depends on how long the key is held down.
+    pub fn action(self, config: &Config) -> Option<Action> {
+        match self {
+            Self::VolumeUp => Some(Action::IncreaseVolume),
+            Self::VolumeDown => Some(Action::DecreaseVolume),
+            Self::VolumeUpFine => Some(Action::IncreaseVolumeFine),
+            Self::VolumeDownfine => Some(Action::DecreaseVolumeFine),
+            Self::Mute => match config.mute_key() {
+                MuteKey::Dim => Some(Action::ToggleDim),
+                MuteKey::Mute => Some(Action::ToggleMute),
+                MuteKey::DimTapMuteHold => None,
+            },
+            Self::Custom(index) => config
+                .hotkeys
+                .get(index)
+                .map(|binding| binding.action.clone()),
+        }
+    }
+}
+
+pub fn register(bindings: &[HotKeyBinding]) -> Result<()> {
+    let mut hotkeys = vec![
+        (HotKey::VolumeUp, HOT_KEY_MODIFIERS::default(), VK_VOLUME_UP),
+        (
+            HotKey::VolumeDown,
+            HOT_KEY_MODIFIERS::default(),
+            VK_VOLUME_DOWN,
+        ),
+        (HotKey::VolumeUpFine, MOD_SHIFT, VK_VOLUME_UP),
+        (HotKey::VolumeDownfine, MOD_SHIFT, VK_VOLUME_DOWN),
+        (HotKey::Mute, HOT_KEY_MODIFIERS::default(), VK_VOLUME_MUTE),
+    ];
+    for (index, binding) in bindings.iter().enumerate() {
+        let key = parse_key(&binding.key).ok_or_else(|| UnknownKeyError(binding.key.clone()))?;
+        hotkeys.push((
+            HotKey::Custom(index),
+            parse_modifiers(&binding.modifiers),
+            key,
+        ));
+    }
+
+    for (hotkey, modifiers, key) in hotkeys {
+        let id = i32::try_from(hotkey.id())?;
+        let result = unsafe { RegisterHotKey(None, id, modifiers, u32::from(key.0)) };
         if !result.as_bool() {
             return Err(HotKeyBindError.into());
         }
@@ -55,6 +114,68 @@ pub const fn receive(msg: &MSG) -> Option<HotKey> {
         3 => Some(HotKey::VolumeUpFine),
         4 => Some(HotKey::VolumeDownfine),
         5 => Some(HotKey::Mute),
+        id if id >= CUSTOM_HOTKEY_ID_START => Some(HotKey::Custom(id - CUSTOM_HOTKEY_ID_START)),
         _ => None,
     }
 }
+
+fn parse_modifiers(modifiers: &[Modifier]) -> HOT_KEY_MODIFIERS {
+    HOT_KEY_MODIFIERS(modifiers.iter().fold(0, |flags, modifier| {
+        flags
+            | match modifier {
+                Modifier::Alt => MOD_ALT.0,
+                Modifier::Ctrl => MOD_CONTROL.0,
+                Modifier::Shift => MOD_SHIFT.0,
+                Modifier::Win => MOD_WIN.0,
+            }
+    }))
+}
+
+fn parse_key(name: &str) -> Option<VIRTUAL_KEY> {
+    let name = name.to_ascii_lowercase();
+
+    // Letters and digits share their virtual key codes with their uppercase ASCII values.
+    if let [character] = name.as_bytes() {
+        if character.is_ascii_alphanumeric() {
+            return Some(VIRTUAL_KEY(u16::from(character.to_ascii_uppercase())));
+        }
+    }
+
+    if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()) {
+        return (1..=24)
+            .contains(&number)
+            .then(|| VIRTUAL_KEY(VK_F1.0 + number - 1));
+    }
+
+    if let Some(number) = name
+        .strip_prefix("numpad")
+        .and_then(|n| n.parse::<u16>().ok())
+    {
+        return (number <= 9).then(|| VIRTUAL_KEY(VK_NUMPAD0.0 + number));
+    }
+
+    let key = match name.as_str() {
+        "volumeup" => VK_VOLUME_UP,
+        "volumedown" => VK_VOLUME_DOWN,
+        "volumemute" => VK_VOLUME_MUTE,
+        "medianexttrack" => VK_MEDIA_NEXT_TRACK,
+        "mediaprevtrack" => VK_MEDIA_PREV_TRACK,
+        "mediastop" => VK_MEDIA_STOP,
+        "mediaplaypause" => VK_MEDIA_PLAY_PAUSE,
+        "up" => VK_UP,
+        "down" => VK_DOWN,
+        "left" => VK_LEFT,
+        "right" => VK_RIGHT,
+        "home" => VK_HOME,
+        "end" => VK_END,
+        "pageup" => VK_PRIOR,
+        "pagedown" => VK_NEXT,
+        "insert" => VK_INSERT,
+        "delete" => VK_DELETE,
+        "space" => VK_SPACE,
+        "pause" => VK_PAUSE,
+        "scrolllock" => VK_SCROLL,
+        _ => return None,
+    };
+    Some(key)
+}
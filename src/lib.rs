@@ -0,0 +1,40 @@
+#![warn(
+    clippy::all,
+    clippy::pedantic,
+    clippy::nursery,
+    clippy::cargo,
+    // clippy::expect_used,
+    // clippy::unwrap_used
+)]
+#![allow(clippy::unreadable_literal)]
+pub mod acceleration;
+pub mod actions;
+pub mod addresses;
+pub mod backend;
+pub mod buttons;
+pub mod channels;
+pub mod comms;
+pub mod config;
+pub mod daw;
+pub mod fader;
+pub mod floats;
+pub mod gesture;
+pub mod manager;
+pub mod meters;
+pub mod presets;
+pub mod ramp;
+pub mod sim;
+pub mod snapshots;
+pub mod supervisor;
+#[cfg(test)]
+mod testing;
+pub mod x32;
+
+#[cfg(feature = "gui")]
+pub mod colors;
+#[cfg(feature = "gui")]
+pub mod gui;
+#[cfg(all(windows, feature = "hotkeys"))]
+pub mod hotkeys;
+#[cfg(feature = "tray")]
+pub mod tray;
@@ -8,17 +8,9 @@
     // clippy::unwrap_used
 )]
 #![allow(clippy::unreadable_literal)]
-mod colors;
-mod comms;
-mod config;
-mod floats;
-mod gui;
-mod hotkeys;
-mod manager;
-mod tray;
-
 use std::{
     net::SocketAddrV4,
+    process,
     sync::{mpsc, Arc},
     thread,
     time::Instant,
@@ -29,7 +21,22 @@ use egui_glow::EguiGlow;
 use glow::{Context, HasContext};
 use glutin::{ContextBuilder, PossiblyCurrent, WindowedContext};
 use parking_lot::Mutex;
-use tray::{MenuAction, Tray};
+use totalmix_volume_control::{
+    acceleration::Accelerator,
+    actions::Action,
+    backend::{MixerBackend, TotalMix},
+    channels::ChannelId,
+    comms::{UdpReceiver, UdpSender},
+    config::{get_user_config, Backend, Config},
+    daw::{Daw, Host},
+    gesture::{Gesture, TapOrHold},
+    gui::VolumeControlApp,
+    hotkeys::{self, HotKey},
+    manager::{Manager, Notification, StateChange},
+    supervisor::Supervisor,
+    tray::{MenuAction, Tray},
+    x32::{Desk, X32},
+};
 use windows::Win32::UI::WindowsAndMessaging::MSG;
 use winit::{
     dpi::{LogicalPosition, LogicalSize},
@@ -39,24 +46,21 @@ use winit::{
     window::WindowBuilder,
 };
 
-use crate::{
-    comms::{UdpReceiver, UdpSender},
-    config::{get_user_config, Config},
-    gui::VolumeControlApp,
-    hotkeys::HotKey,
-    manager::Manager,
-};
-
 #[derive(Debug)]
 pub enum UserEvent {
-    HotKeyPressed,
+    /// Displays the window, naming the channel that the action applies to (if any) in the heading.
+    ShowRequested(Option<ChannelId>),
+    StateChanged(Notification),
 }
 
 fn main() {
     // Load the configuration.
     let config = match get_user_config() {
         Ok(config) => config,
-        Err(_) => Config::default(),
+        Err(error) => {
+            eprintln!("unable to load the configuration: {error:#}");
+            process::exit(1);
+        }
     };
     let config = Arc::new(config);
 
@@ -74,77 +78,120 @@ fn main() {
     let event_loop_proxy = Arc::new(Mutex::new(event_loop.create_proxy()));
 
     // Create the system tray.
-    let tray = Tray::new().unwrap();
+    let mut tray = Tray::new(
+        &config.tray,
+        &config.presets,
+        config.osc.backend == Backend::TotalMix,
+    )
+    .unwrap();
 
     // Create the window and OpenGL context.
     let (gl_window, gl) = create_display(&event_loop, &config).unwrap();
     let gl = Arc::new(gl);
 
     // Create the volume manager.
-    let sender = UdpSender::new(SocketAddrV4::new(
-        config.osc.outgoing_hostname.parse().unwrap(),
-        config.osc.outgoing_port,
-    ))
-    .unwrap();
-    let receiver = UdpReceiver::bind(SocketAddrV4::new(
-        config.osc.incoming_hostname.parse().unwrap(),
-        config.osc.incoming_port,
-    ))
-    .unwrap();
-    let mut manager = Manager::default();
-    manager.set_sender(sender);
-    manager.set_receiver(receiver);
-    manager.set_increment(config.volume.increment).unwrap();
-    manager
-        .set_fine_increment(config.volume.fine_increment)
-        .unwrap();
-    manager.set_max_volume(config.volume.max_volume).unwrap();
-    let manager = Arc::new(manager);
+    let manager = Arc::new(create_manager(&config).unwrap());
 
     // Create the application.
     let mut egui_glow = EguiGlow::new(&event_loop, Arc::clone(&gl));
-    let mut app = VolumeControlApp::new(
-        &egui_glow.egui_ctx,
-        Arc::clone(&manager),
-        Arc::clone(&config),
-    );
+    let mut app = VolumeControlApp::new(&egui_glow.egui_ctx, Arc::clone(&config)).unwrap();
 
     // Register global hotkeys.
-    hotkeys::register().unwrap();
+    hotkeys::register(&config.hotkeys).unwrap();
 
-    // Create the thread that will receive volume changes from the device.
+    // Create the thread that will forward changes in the state of the device to the event loop.
     {
-        let manager = Arc::clone(&manager);
+        let changes = manager.subscribe();
+        let event_loop_proxy = Arc::clone(&event_loop_proxy);
         thread::Builder::new()
-            .name("receiver".to_string())
+            .name("notifier".to_string())
             .spawn(move || {
-                manager.request_volume().unwrap();
-                loop {
-                    manager.recieve_volume().unwrap();
+                for notification in changes {
+                    event_loop_proxy
+                        .lock()
+                        .send_event(UserEvent::StateChanged(notification))
+                        .unwrap();
                 }
             })
             .unwrap();
     }
 
+    // Create the thread that will receive volume changes from the device.
+    {
+        let manager = Arc::clone(&manager);
+        let mut supervisor = create_supervisor(&config).unwrap();
+        thread::Builder::new()
+            .name("receiver".to_string())
+            .spawn(move || supervisor.run(&manager))
+            .unwrap();
+    }
+
+    // Create the thread that will send ramped and rate-limited volume changes to the device.
+    {
+        let manager = Arc::clone(&manager);
+        thread::Builder::new()
+            .name("scheduler".to_string())
+            .spawn(move || manager.run_scheduler())
+            .unwrap();
+    }
+
     // Create the thread that will send volume changes to the device.
     {
         let manager = Arc::clone(&manager);
+        let config = Arc::clone(&config);
         let event_loop_proxy = Arc::clone(&event_loop_proxy);
+        let mut accelerator = create_accelerator(&config).unwrap();
+        let mut mute_gesture = TapOrHold::new(config.volume.mute_hold_time().unwrap());
         thread::Builder::new()
             .name("sender".to_string())
             .spawn(move || loop {
-                let hotkey = hotkey_receiver.recv().unwrap();
+                // Wake up when the mute key may have been released so that a tap is acted upon.
+                let hotkey = match mute_gesture.deadline() {
+                    Some(deadline) => match hotkey_receiver
+                        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    {
+                        Err(mpsc::RecvTimeoutError::Timeout) => None,
+                        result => Some(result.unwrap()),
+                    },
+                    None => Some(hotkey_receiver.recv().unwrap()),
+                };
+
+                let now = Instant::now();
+
+                // A tap of the mute key is only known once the key has been released.
+                if mute_gesture.poll(now) == Some(Gesture::Tap) {
+                    if let Err(error) = manager.toggle_dim() {
+                        eprintln!("unable to toggle dim: {error}");
+                    }
+                }
+
+                let hotkey = match hotkey {
+                    Some(hotkey) => hotkey,
+                    None => continue,
+                };
+                let action = hotkey.action(&config);
                 event_loop_proxy
                     .lock()
-                    .send_event(UserEvent::HotKeyPressed)
+                    .send_event(UserEvent::ShowRequested(
+                        action
+                            .as_ref()
+                            .and_then(|action| action_channel(&manager, action)),
+                    ))
                     .unwrap();
-                match hotkey {
-                    HotKey::VolumeUp => manager.increase_volume().unwrap(),
-                    HotKey::VolumeDown => manager.decrease_volume().unwrap(),
-                    HotKey::VolumeUpFine => manager.increase_volume_fine().unwrap(),
-                    HotKey::VolumeDownfine => manager.decrease_volume_fine().unwrap(),
-                    HotKey::Mute => manager.toggle_dim().unwrap(),
+                let action = match action {
+                    Some(action) => action,
+                    // The mute key dims when tapped and mutes when held down.
+                    None => match mute_gesture.press(now) {
+                        Some(Gesture::Hold) => Action::ToggleMute,
+                        _ => continue,
+                    },
                 };
+                let multiplier = accelerator
+                    .as_mut()
+                    .map_or(1.0, |accelerator| accelerator.press(hotkey, now));
+                if let Err(error) = action.perform_scaled(&manager, multiplier) {
+                    eprintln!("unable to perform {action:?}: {error}");
+                }
             })
             .unwrap();
     }
@@ -152,6 +199,14 @@ fn main() {
     // Run the event loop.
     let mut visible = false;
     event_loop.run(move |event, _target, control_flow| {
+        if let Event::UserEvent(UserEvent::StateChanged(notification)) = &event {
+            app.apply_change(&notification.change);
+        }
+        if let Event::UserEvent(UserEvent::ShowRequested(channel)) = &event {
+            app.set_heading_channel(*channel);
+        }
+        let shown = app.shown();
+
         let mut redraw = |restart| {
             let repaint_after = egui_glow.run(gl_window.window(), |egui_ctx| {
                 app.draw(egui_ctx, restart);
@@ -192,8 +247,38 @@ fn main() {
             Event::RedrawEventsCleared if cfg!(windows) => redraw(false),
             Event::RedrawRequested(_) if !cfg!(windows) => redraw(false),
 
-            // Restart the animation sequence to display the window when a hotkey is pressed.
-            Event::UserEvent(UserEvent::HotKeyPressed) => redraw(true),
+            // Restart the animation sequence to display the window when a hotkey or tray action is used.
+            Event::UserEvent(UserEvent::ShowRequested(_)) => redraw(true),
+
+            // Reflect changes in the state of the device in the tray and the window, displaying
+            // the window if the volume or a button was changed or a snapshot was recalled externally.
+            Event::UserEvent(UserEvent::StateChanged(notification)) => {
+                match &notification.change {
+                    StateChange::Connection(state) => {
+                        tray.set_tooltip(&format!("TotalMix Volume Control ({state})"))
+                            .unwrap();
+                    }
+                    StateChange::SnapshotNamed(snapshot, name) => {
+                        tray.set_snapshot_name(*snapshot, name);
+                    }
+                    _ => (),
+                }
+
+                if notification.external
+                    && config.interface.show_on_external_change
+                    && matches!(
+                        notification.change,
+                        StateChange::Volume(..)
+                            | StateChange::Button(..)
+                            | StateChange::SnapshotRecalled(..)
+                    )
+                {
+                    redraw(true);
+                } else if shown || !matches!(notification.change, StateChange::Levels(_)) {
+                    // The meters are only drawn while the window is shown.
+                    gl_window.window().request_redraw();
+                }
+            }
 
             Event::WindowEvent { event, .. } => {
                 if event == WindowEvent::Destroyed {
@@ -222,12 +307,119 @@ fn main() {
 
         if let Some(tray_menu_item) = tray.receive_menu_event() {
             match tray_menu_item {
+                MenuAction::Perform(action) => {
+                    event_loop_proxy
+                        .lock()
+                        .send_event(UserEvent::ShowRequested(action_channel(&manager, &action)))
+                        .unwrap();
+                    if let Err(error) = action.perform(&manager) {
+                        eprintln!("unable to perform {action:?}: {error}");
+                    }
+                }
                 MenuAction::Exit => *control_flow = ControlFlow::Exit,
             }
         }
     });
 }
 
+fn action_channel(manager: &Manager<UdpSender, UdpReceiver>, action: &Action) -> Option<ChannelId> {
+    action
+        .channel()
+        .and_then(|channel| manager.channel_id(channel).ok())
+}
+
+fn create_manager(config: &Config) -> Result<Manager<UdpSender, UdpReceiver>> {
+    let backend: Box<dyn MixerBackend> = match config.osc.backend {
+        Backend::TotalMix => Box::new(TotalMix::new(config.osc.addresses.clone())?),
+        Backend::X32 => Box::new(X32::new(Desk::X32)),
+        Backend::XAir => Box::new(X32::new(Desk::XAir)),
+        Backend::Reaper => Box::new(Daw::new(Host::Reaper)),
+        Backend::Ardour => Box::new(Daw::new(Host::Ardour)),
+    };
+    let outgoing_addr = SocketAddrV4::new(
+        config.osc.outgoing_hostname.parse()?,
+        config.osc.outgoing_port,
+    );
+    let receiver = UdpReceiver::bind(SocketAddrV4::new(
+        config.osc.incoming_hostname.parse()?,
+        config.osc.incoming_port,
+    ))?;
+    let sender = if backend.replies_to_sender() {
+        receiver.sender(outgoing_addr)?
+    } else {
+        UdpSender::new(outgoing_addr)?
+    };
+
+    let mut manager = Manager::default();
+    manager.set_backend(backend);
+    manager.set_sender(sender);
+    manager.set_receiver(receiver);
+    match config.volume.increment_db {
+        Some(increment_db) => manager.set_increment_db(increment_db)?,
+        None => manager.set_increment(config.volume.increment)?,
+    }
+    match config.volume.fine_increment_db {
+        Some(fine_increment_db) => manager.set_fine_increment_db(fine_increment_db)?,
+        None => manager.set_fine_increment(config.volume.fine_increment)?,
+    }
+    manager.set_max_volume(
+        config
+            .volume
+            .max_volume_db
+            .map_or(config.volume.max_volume, |max_volume_db| {
+                manager.db_to_volume(max_volume_db)
+            }),
+    )?;
+    if let Some(min_volume_db) = config.volume.min_volume_db {
+        manager.set_min_volume(manager.db_to_volume(min_volume_db))?;
+    }
+    manager.set_db_precision(config.volume.db_precision)?;
+    manager.set_db_negative_infinity(&config.volume.db_negative_infinity);
+    manager.set_max_send_rate(config.osc.max_send_rate)?;
+    manager.set_mute_fade(config.volume.mute_fade()?)?;
+    manager.set_ramp_duration(config.volume.ramp_duration()?)?;
+    manager.set_ramp_curve(config.volume.ramp_curve);
+    manager.set_reference_volume(manager.db_to_volume(config.volume.reference_volume_db))?;
+    manager.set_bank_size(config.osc.bank_size)?;
+    manager.set_meters_enabled(config.interface.show_meters);
+    for channel in &config.channels {
+        manager.add_channel(&channel.name, channel.id())?;
+    }
+    for preset in &config.presets {
+        manager.add_preset(preset.clone())?;
+    }
+    for binding in &config.hotkeys {
+        binding.action.validate(&manager)?;
+    }
+
+    Ok(manager)
+}
+
+fn create_supervisor(config: &Config) -> Result<Supervisor> {
+    let mut supervisor = Supervisor::default();
+    supervisor.set_device_timeout(config.osc.device_timeout()?)?;
+    supervisor.set_reconnect_interval(
+        config.osc.reconnect_interval()?,
+        config.osc.max_reconnect_interval()?,
+    )?;
+
+    Ok(supervisor)
+}
+
+fn create_accelerator(config: &Config) -> Result<Option<Accelerator<HotKey>>> {
+    if !config.acceleration.enabled {
+        return Ok(None);
+    }
+
+    let mut accelerator = Accelerator::default();
+    accelerator.set_curve(config.acceleration.curve);
+    accelerator.set_rate(config.acceleration.rate)?;
+    accelerator.set_max_multiplier(config.acceleration.max_multiplier)?;
+    accelerator.set_reset_after(config.acceleration.reset_after()?)?;
+
+    Ok(Some(accelerator))
+}
+
 fn create_display(
     event_loop: &EventLoop<UserEvent>,
     config: &Config,
@@ -1,18 +1,34 @@
-use std::{fmt, io};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io, mem,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 use rosc::{OscMessage, OscPacket, OscType};
 use thiserror::Error;
 
 use crate::{
+    addresses::Control,
+    backend::{number, MixerBackend, TotalMix},
+    buttons::{Button, ButtonState},
+    channels::{Bus, Channel, ChannelId, Mixer, Strip},
     comms::{Receiver, Sender},
+    fader,
     floats::RoughEq,
+    meters::Levels,
+    presets::Preset,
+    ramp::{Curve, Ramp},
+    snapshots::{self, SnapshotChange, SnapshotControl, Snapshots},
 };
 
-const VOLUME_OSC_ADDR: &str = "/1/mastervolume";
-const VOLUME_DECIBELS_OSC_ADDR: &str = "/1/mastervolumeVal";
-const DIM_OSC_ADDR: &str = "/1/mainDim";
+// Received volumes within this tolerance of a volume we sent shortly beforehand are considered to
+// be echoes of our own write rather than a change made on the device.
+const ECHO_TOLERANCE: f32 = 0.005;
+const ECHO_WINDOW: Duration = Duration::from_secs(1);
 
 #[derive(Error, Debug)]
 #[error("increment must be greater than 0 and no more than 0.1")]
@@ -22,17 +38,248 @@ struct IncrementRangeError;
 #[error("fine increment must be greater than 0 and no more than 0.05")]
 struct FineIncrementRangeError;
 
+#[derive(Error, Debug)]
+#[error("increment in decibels must be greater than 0 and no more than 6.0")]
+struct IncrementDbRangeError;
+
+#[derive(Error, Debug)]
+#[error("fine increment in decibels must be greater than 0 and no more than 3.0")]
+struct FineIncrementDbRangeError;
+
 #[derive(Error, Debug)]
 #[error("max volume must be no more than 1.0")]
 struct MaxVolumeRangeError;
 
+#[derive(Error, Debug)]
+#[error("min volume must be no less than 0.0 and no more than the max volume")]
+struct MinVolumeRangeError;
+
+#[derive(Error, Debug)]
+#[error("decibel precision must be no more than 3")]
+struct DbPrecisionRangeError;
+
+#[derive(Error, Debug)]
+#[error("ramp duration must be no more than 10 seconds")]
+struct RampDurationRangeError;
+
+#[derive(Error, Debug)]
+#[error("max send rate must be greater than 0 and no more than 1000")]
+struct MaxSendRateRangeError;
+
+#[derive(Error, Debug)]
+#[error("mute fade must be no more than 10 seconds")]
+struct MuteFadeRangeError;
+
+#[derive(Error, Debug)]
+#[error("bank size must be no less than 1 and no more than 48")]
+struct BankSizeRangeError;
+
+#[derive(Error, Debug)]
+#[error("channel number must be no less than 1 and no more than 198")]
+struct ChannelNumberRangeError;
+
+#[derive(Error, Debug)]
+#[error("no channel is named {0:?}")]
+struct UnknownChannelError(String);
+
+#[derive(Error, Debug)]
+#[error("reference volume must be no less than 0.0 and no more than 1.0")]
+struct ReferenceVolumeRangeError;
+
+#[derive(Error, Debug)]
+#[error("preset {0:?} must have a volume or a volume in decibels")]
+struct PresetVolumeError(String);
+
+#[derive(Error, Debug)]
+#[error("preset {0:?} is defined more than once")]
+struct DuplicatePresetError(String);
+
+#[derive(Error, Debug)]
+#[error("preset ramp time must be no less than 0 and no more than 60 seconds")]
+struct PresetRampTimeRangeError;
+
+#[derive(Error, Debug)]
+#[error("no preset is named {0:?}")]
+struct UnknownPresetError(String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Nothing has been requested from or received from the device.
+    Disconnected,
+    /// The current state has been requested but the device hasn't responded yet.
+    Requesting,
+    /// The device has been heard from recently.
+    Connected,
+    /// The device was previously heard from but has since gone quiet.
+    Stale { last_seen: Instant },
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disconnected => write!(f, "disconnected"),
+            Self::Requesting => write!(f, "connecting"),
+            Self::Connected => write!(f, "connected"),
+            Self::Stale { .. } => write!(f, "not responding"),
+        }
+    }
+}
+
+/// The listening level (A) and the calibrated reference level (B) that the volume may be toggled
+/// between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceSide {
+    A,
+    B,
+}
+
+/// The active side along with the remembered volume of the other side.
+#[derive(Debug, Clone, Copy)]
+struct Reference {
+    side: ReferenceSide,
+    other_volume: f32,
+}
+
+/// A change in the state of the device, published to all subscribers of the manager.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    /// The volume along with its decibel readout, which are published together so that they are
+    /// never seen half-updated.
+    Volume(f32, Option<String>),
+    /// The decibel readout when it changed without the volume changing.
+    VolumeDb(Option<String>),
+    Button(Button, bool),
+    Muted(bool),
+    Channel(ChannelId, Channel),
+    Levels(Levels),
+    PresetRecalled(String),
+    Reference(ReferenceSide),
+    /// A snapshot was recalled, along with its name if it is known.
+    SnapshotRecalled(usize, Option<String>),
+    SnapshotNamed(usize, String),
+    Connection(ConnectionState),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub change: StateChange,
+    /// Whether the change was made on the device itself or in `TotalMix` (as opposed to being made
+    /// by the manager or being the initial state received from the device).
+    pub external: bool,
+}
+
+/// The amount to step the volume by, either in normalised fader units or in decibels.
+#[derive(Debug, Clone, Copy)]
+enum Increment {
+    Volume(f32),
+    Decibels(f32),
+}
+
+impl Increment {
+    fn scaled(self, multiplier: f32) -> Self {
+        match self {
+            Self::Volume(increment) => Self::Volume(increment * multiplier),
+            Self::Decibels(increment) => Self::Decibels(increment * multiplier),
+        }
+    }
+
+    // Steps in decibels follow the fader law of the mixer.
+    fn increase(self, volume: f32, backend: &dyn MixerBackend) -> f32 {
+        match self {
+            Self::Volume(increment) => volume + increment,
+            // Stepping up from -oo starts at the lowest level on the fader.
+            Self::Decibels(increment) => backend.db_to_volume(
+                backend
+                    .volume_to_db(volume)
+                    .max(backend.min_db() - increment)
+                    + increment,
+            ),
+        }
+    }
+
+    fn decrease(self, volume: f32, backend: &dyn MixerBackend) -> f32 {
+        match self {
+            Self::Volume(increment) => volume - increment,
+            Self::Decibels(increment) => {
+                let db = backend.volume_to_db(volume) - increment;
+                if db < backend.min_db() {
+                    0.0
+                } else {
+                    backend.db_to_volume(db)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Connection {
+    requested: bool,
+    last_seen: Option<Instant>,
+    stale: bool,
+}
+
+/// The volume writes that are waiting to be sent to the device, which are limited to the max send
+/// rate so that `TotalMix` isn't flooded while the volume keys are repeating or a ramp is running.
+#[derive(Debug, Default)]
+struct Schedule {
+    ramp: Option<Ramp>,
+    /// Whether the ramp is a fade (e.g. the sleep timer) that steps take over from instead of
+    /// building upon.
+    fading: bool,
+    pending_volume: Option<f32>,
+    last_write: Option<Instant>,
+}
+
+impl Schedule {
+    fn write_due(&self, now: Instant, send_interval: Duration) -> bool {
+        self.last_write.map_or(true, |last_write| {
+            now.duration_since(last_write) >= send_interval
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Echo {
+    /// The volume wasn't recently sent by the manager.
+    None,
+    /// The volume is the latest one sent by the manager.
+    Latest,
+    /// The volume was sent by the manager but has since been superseded by another.
+    Superseded,
+}
+
 pub struct Manager<S: Sender, R: Receiver> {
-    increment: f32,
-    fine_increment: f32,
+    increment: Increment,
+    fine_increment: Increment,
     max_volume: f32,
+    min_volume: f32,
+    db_precision: usize,
+    db_negative_infinity: String,
+    ramp_duration: Duration,
+    ramp_curve: Curve,
+    send_interval: Duration,
+    mute_fade: Duration,
+    meters_enabled: bool,
+    backend: Box<dyn MixerBackend>,
+    last_keep_alive: Mutex<Option<Instant>>,
     volume: Mutex<f32>,
     volume_db: Mutex<Option<String>>,
-    dim: Mutex<f32>,
+    buttons: Mutex<HashMap<Button, ButtonState>>,
+    muted_volume: Mutex<Option<f32>>,
+    device_muted: Mutex<Option<bool>>,
+    reference: Mutex<Reference>,
+    named_channels: HashMap<String, ChannelId>,
+    presets: HashMap<String, Preset>,
+    mixer: Mutex<Mixer>,
+    levels: Mutex<Levels>,
+    snapshots: Mutex<Snapshots>,
+    connection: Mutex<Connection>,
+    last_connection_state: Mutex<ConnectionState>,
+    sent_volumes: Mutex<VecDeque<(f32, Instant)>>,
+    schedule: Mutex<Schedule>,
+    schedule_changed: Condvar,
+    subscribers: Mutex<Vec<mpsc::Sender<Notification>>>,
     sender: Option<S>,
     receiver: Option<R>,
 }
@@ -43,9 +290,12 @@ impl<S: Sender, R: Receiver> fmt::Debug for Manager<S, R> {
             .field("increment", &self.increment)
             .field("fine_increment", &self.fine_increment)
             .field("max_volume", &self.max_volume)
+            .field("min_volume", &self.min_volume)
             .field("volume", &self.volume())
             .field("volume_db", &self.volume_db())
             .field("dimmed", &self.dimmed())
+            .field("muted", &self.muted())
+            .field("connection_state", &self.connection_state())
             .finish()
     }
 }
@@ -53,12 +303,39 @@ impl<S: Sender, R: Receiver> fmt::Debug for Manager<S, R> {
 impl<S: Sender, R: Receiver> Default for Manager<S, R> {
     fn default() -> Self {
         Self {
-            increment: 0.02,
-            fine_increment: 0.01,
+            increment: Increment::Volume(0.02),
+            fine_increment: Increment::Volume(0.01),
             max_volume: 1.0,
+            min_volume: 0.0,
+            db_precision: 1,
+            db_negative_infinity: "-oo".to_string(),
+            ramp_duration: Duration::ZERO,
+            ramp_curve: Curve::default(),
+            send_interval: Duration::from_millis(20),
+            mute_fade: Duration::ZERO,
+            meters_enabled: false,
+            backend: Box::new(TotalMix::default()),
+            last_keep_alive: Mutex::new(None),
             volume: Mutex::new(-1.0),
             volume_db: Mutex::new(None),
-            dim: Mutex::new(-1.0),
+            buttons: Mutex::new(HashMap::new()),
+            muted_volume: Mutex::new(None),
+            device_muted: Mutex::new(None),
+            reference: Mutex::new(Reference {
+                side: ReferenceSide::A,
+                other_volume: fader::db_to_volume(-18.0),
+            }),
+            named_channels: HashMap::new(),
+            presets: HashMap::new(),
+            mixer: Mutex::new(Mixer::default()),
+            levels: Mutex::new(Levels::default()),
+            snapshots: Mutex::new(Snapshots::default()),
+            connection: Mutex::new(Connection::default()),
+            last_connection_state: Mutex::new(ConnectionState::Disconnected),
+            sent_volumes: Mutex::new(VecDeque::new()),
+            schedule: Mutex::new(Schedule::default()),
+            schedule_changed: Condvar::new(),
+            subscribers: Mutex::new(Vec::new()),
             sender: None,
             receiver: None,
         }
@@ -74,22 +351,63 @@ impl<S: Sender, R: Receiver> Manager<S, R> {
         self.receiver = Some(receiver);
     }
 
+    /// Sets the step of the volume keys as a fraction of the fader's travel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the increment is out of range.
     pub fn set_increment(&mut self, increment: f32) -> Result<()> {
         if !(0.0..=0.10).contains(&increment) {
             return Err(IncrementRangeError.into());
         }
-        self.increment = increment;
+        self.increment = Increment::Volume(increment);
+        Ok(())
+    }
+
+    /// Sets the step of the volume keys in decibels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the increment is out of range.
+    pub fn set_increment_db(&mut self, increment_db: f32) -> Result<()> {
+        if !(0.0..=6.0).contains(&increment_db) {
+            return Err(IncrementDbRangeError.into());
+        }
+        self.increment = Increment::Decibels(increment_db);
         Ok(())
     }
 
+    /// Sets the step of the volume keys while shift is held as a fraction of the fader's travel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fine increment is out of range.
     pub fn set_fine_increment(&mut self, fine_increment: f32) -> Result<()> {
         if !(0.0..=0.05).contains(&fine_increment) {
             return Err(FineIncrementRangeError.into());
         }
-        self.fine_increment = fine_increment;
+        self.fine_increment = Increment::Volume(fine_increment);
+        Ok(())
+    }
+
+    /// Sets the step of the volume keys while shift is held in decibels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fine increment is out of range.
+    pub fn set_fine_increment_db(&mut self, fine_increment_db: f32) -> Result<()> {
+        if !(0.0..=3.0).contains(&fine_increment_db) {
+            return Err(FineIncrementDbRangeError.into());
+        }
+        self.fine_increment = Increment::Decibels(fine_increment_db);
         Ok(())
     }
 
+    /// Sets the highest normalised fader position that is sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the maximum volume is out of range.
     pub fn set_max_volume(&mut self, max_volume: f32) -> Result<()> {
         if !(0.0..=1.0).contains(&max_volume) {
             return Err(MaxVolumeRangeError.into());
@@ -98,6 +416,165 @@ impl<S: Sender, R: Receiver> Manager<S, R> {
         Ok(())
     }
 
+    /// Sets the lowest normalised fader position that stepping or fading the volume reaches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the minimum volume is negative or above the maximum volume.
+    pub fn set_min_volume(&mut self, min_volume: f32) -> Result<()> {
+        if !(0.0..=self.max_volume).contains(&min_volume) {
+            return Err(MinVolumeRangeError.into());
+        }
+        self.min_volume = min_volume;
+        Ok(())
+    }
+
+    /// Sets the number of decimal places of the readouts derived from the volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the precision is out of range.
+    pub fn set_db_precision(&mut self, db_precision: usize) -> Result<()> {
+        if db_precision > 3 {
+            return Err(DbPrecisionRangeError.into());
+        }
+        self.db_precision = db_precision;
+        Ok(())
+    }
+
+    pub fn set_db_negative_infinity(&mut self, db_negative_infinity: &str) {
+        self.db_negative_infinity = db_negative_infinity.to_string();
+    }
+
+    /// Sets the duration over which each change of the volume is ramped, where a duration of 0
+    /// changes the volume instantly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the duration is out of range.
+    pub fn set_ramp_duration(&mut self, ramp_duration: Duration) -> Result<()> {
+        if ramp_duration > Duration::from_secs(10) {
+            return Err(RampDurationRangeError.into());
+        }
+        self.ramp_duration = ramp_duration;
+        Ok(())
+    }
+
+    pub const fn set_ramp_curve(&mut self, ramp_curve: Curve) {
+        self.ramp_curve = ramp_curve;
+    }
+
+    /// Sets the duration over which the volume is faded when muting and unmuting, where a duration
+    /// of 0 mutes and unmutes instantly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the duration is out of range.
+    pub fn set_mute_fade(&mut self, mute_fade: Duration) -> Result<()> {
+        if mute_fade > Duration::from_secs(10) {
+            return Err(MuteFadeRangeError.into());
+        }
+        self.mute_fade = mute_fade;
+        Ok(())
+    }
+
+    /// Sets whether the meters are decoded and published, which is only worthwhile while they're
+    /// shown since the device sends them so frequently.
+    pub const fn set_meters_enabled(&mut self, meters_enabled: bool) {
+        self.meters_enabled = meters_enabled;
+    }
+
+    /// Sets the maximum number of volume writes sent to the device per second, where any writes in
+    /// between are coalesced into the latest volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rate is out of range.
+    pub fn set_max_send_rate(&mut self, max_send_rate: f32) -> Result<()> {
+        if max_send_rate <= 0.0 || max_send_rate > 1000.0 {
+            return Err(MaxSendRateRangeError.into());
+        }
+        self.send_interval = Duration::from_secs_f32(max_send_rate.recip());
+        Ok(())
+    }
+
+    /// Sets the mixer that is controlled, which defaults to `TotalMix` FX.
+    pub fn set_backend(&mut self, backend: Box<dyn MixerBackend>) {
+        self.backend = backend;
+    }
+
+    /// Sets the number of channels shown on page 1 at a time, which must match the number of
+    /// faders per bank configured in the OSC settings of `TotalMix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bank size is out of range.
+    pub fn set_bank_size(&mut self, bank_size: usize) -> Result<()> {
+        if !(1..=48).contains(&bank_size) {
+            return Err(BankSizeRangeError.into());
+        }
+        self.mixer.get_mut().set_bank_size(bank_size);
+        Ok(())
+    }
+
+    /// Names a channel so that it may be referred to by name (e.g. by a hotkey).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range.
+    pub fn add_channel(&mut self, name: &str, channel: ChannelId) -> Result<()> {
+        if !channel.is_valid() {
+            return Err(ChannelNumberRangeError.into());
+        }
+        self.named_channels.insert(name.to_string(), channel);
+        Ok(())
+    }
+
+    /// Sets the calibrated reference level that is toggled to from the listening level.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reference volume is out of range.
+    pub fn set_reference_volume(&mut self, reference_volume: f32) -> Result<()> {
+        if !(0.0..=1.0).contains(&reference_volume) {
+            return Err(ReferenceVolumeRangeError.into());
+        }
+        *self.reference.get_mut() = Reference {
+            side: ReferenceSide::A,
+            other_volume: reference_volume,
+        };
+        Ok(())
+    }
+
+    /// Adds a preset that may be recalled by its name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the preset has no volume, its ramp time is out of range or a preset with
+    /// the same name was already added.
+    pub fn add_preset(&mut self, preset: Preset) -> Result<()> {
+        if preset.volume.is_none() && preset.volume_db.is_none() {
+            return Err(PresetVolumeError(preset.name).into());
+        }
+        if preset
+            .ramp_time
+            .map_or(false, |ramp_time| !(0.0..=60.0).contains(&ramp_time))
+        {
+            return Err(PresetRampTimeRangeError.into());
+        }
+        if self.presets.contains_key(&preset.name) {
+            return Err(DuplicatePresetError(preset.name).into());
+        }
+        self.presets.insert(preset.name.clone(), preset);
+        Ok(())
+    }
+
+    pub fn subscribe(&self) -> mpsc::Receiver<Notification> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().push(sender);
+        receiver
+    }
+
     pub fn volume(&self) -> f32 {
         let volume = self.volume.lock();
         *volume
@@ -109,146 +586,1780 @@ impl<S: Sender, R: Receiver> Manager<S, R> {
     }
 
     pub fn dimmed(&self) -> bool {
-        self.dim().roughly_eq(1.0)
+        self.button_active(Button::Dim)
+    }
+
+    pub fn button_active(&self, button: Button) -> bool {
+        self.buttons
+            .lock()
+            .get(&button)
+            .map_or(false, ButtonState::active)
     }
 
-    fn dim(&self) -> f32 {
-        let dim = self.dim.lock();
-        *dim
+    pub fn muted(&self) -> bool {
+        self.muted_volume.lock().is_some() || *self.device_muted.lock() == Some(true)
+    }
+
+    /// Whether the mixer has the control.
+    pub fn supports(&self, control: Control) -> bool {
+        self.backend.supports(control)
+    }
+
+    /// Converts decibels into a normalised fader position using the fader law of the mixer.
+    pub fn db_to_volume(&self, db: f32) -> f32 {
+        self.backend.db_to_volume(db)
+    }
+
+    pub fn reference_side(&self) -> ReferenceSide {
+        self.reference.lock().side
+    }
+
+    /// The preset with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no preset with the name.
+    pub fn preset(&self, name: &str) -> Result<&Preset> {
+        self.presets
+            .get(name)
+            .ok_or_else(|| UnknownPresetError(name.to_string()).into())
+    }
+
+    /// The channel with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no channel with the name.
+    pub fn channel_id(&self, name: &str) -> Result<ChannelId> {
+        self.named_channels
+            .get(name)
+            .copied()
+            .ok_or_else(|| UnknownChannelError(name.to_string()).into())
+    }
+
+    pub fn channel(&self, channel: ChannelId) -> Option<Channel> {
+        self.mixer.lock().channel(channel).cloned()
+    }
+
+    pub fn levels(&self) -> Levels {
+        let levels = self.levels.lock();
+        *levels
+    }
+
+    pub fn snapshot_name(&self, snapshot: usize) -> Option<String> {
+        self.snapshots
+            .lock()
+            .name(snapshot)
+            .map(ToString::to_string)
+    }
+
+    pub fn active_snapshot(&self) -> Option<usize> {
+        self.snapshots.lock().active()
+    }
+
+    pub fn bus(&self) -> Option<Bus> {
+        self.mixer.lock().bus()
+    }
+
+    pub fn bank_start(&self) -> Option<usize> {
+        self.mixer.lock().bank_start()
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        let connection = self.connection.lock();
+        match connection.last_seen {
+            Some(last_seen) if connection.stale => ConnectionState::Stale { last_seen },
+            Some(_) => ConnectionState::Connected,
+            None if connection.requested => ConnectionState::Requesting,
+            None => ConnectionState::Disconnected,
+        }
+    }
+
+    /// Marks the device as not responding until it is heard from again (e.g. when it hasn't
+    /// answered a probe).
+    pub fn mark_stale(&self) {
+        {
+            let mut connection = self.connection.lock();
+            if connection.last_seen.is_none() {
+                return;
+            }
+            connection.stale = true;
+        }
+        self.refresh_connection_state();
+    }
+
+    /// Publishes the connection state if it has changed since it was last published.
+    fn refresh_connection_state(&self) {
+        let state = self.connection_state();
+        let mut last_state = self.last_connection_state.lock();
+        if mem::discriminant(&state) != mem::discriminant(&*last_state) {
+            *last_state = state;
+            self.publish(StateChange::Connection(state), false);
+        }
     }
 
     pub fn initialized(&self) -> bool {
-        self.volume().roughly_ne(-1.0) && self.volume_db().is_some() && self.dim().roughly_ne(-1.0)
+        self.volume().roughly_ne(-1.0)
+            && (!self.supports(Control::Button(Button::Dim))
+                || self
+                    .buttons
+                    .lock()
+                    .get(&Button::Dim)
+                    .map_or(false, ButtonState::known))
+            && (!self.supports(Control::Mute) || self.device_muted.lock().is_some())
     }
 
+    pub fn reset(&self) {
+        *self.schedule.lock() = Schedule::default();
+        *self.volume.lock() = -1.0;
+        *self.volume_db.lock() = None;
+        *self.device_muted.lock() = None;
+        self.buttons.lock().clear();
+        self.mixer.lock().clear();
+        *self.levels.lock() = Levels::default();
+        *self.snapshots.lock() = Snapshots::default();
+        *self.connection.lock() = Connection::default();
+        self.refresh_connection_state();
+    }
+
+    /// Requests the current state of the mixer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
     pub fn request_volume(&self) -> Result<()> {
-        self.send(VOLUME_OSC_ADDR, -1.0)?;
-        self.send(DIM_OSC_ADDR, -1.0)
+        self.connection.lock().requested = true;
+        self.refresh_connection_state();
+        for message in self.backend.request() {
+            self.send_message(message)?;
+        }
+        *self.last_keep_alive.lock() = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Reminds the mixer to keep sending updates if it needs to be reminded and it's time to do
+    /// so.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn keep_alive(&self) -> Result<()> {
+        let interval = match self.backend.keep_alive_interval() {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+        let now = Instant::now();
+        let mut last_keep_alive = self.last_keep_alive.lock();
+        if last_keep_alive.map_or(false, |last_keep_alive| {
+            now.duration_since(last_keep_alive) < interval
+        }) {
+            return Ok(());
+        }
+        for message in self.backend.keep_alive() {
+            self.send_message(message)?;
+        }
+        *last_keep_alive = Some(now);
+        Ok(())
     }
 
+    /// Receives the next update from the mixer, returning whether anything was received.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the receiver couldn't be read.
     pub fn recieve_volume(&self) -> Result<bool> {
         let receiver = match self.receiver.as_ref() {
             Some(receiver) => receiver,
             None => return Err(io::Error::from(io::ErrorKind::NotConnected).into()),
         };
-        let packet = receiver.receive()?;
+        let packet = match receiver.receive()? {
+            Some(packet) => packet,
+            None => return Ok(false),
+        };
+        let initialized = self.initialized();
         let mut received = false;
+        let mut updated_volume = None;
+        let mut received_volume_db = None;
+        let mut superseded_volume = false;
+        let mut updated_levels = None;
 
-        if let OscPacket::Bundle(bundle) = packet {
-            for packet in bundle.content {
-                if let OscPacket::Message(message) = packet {
-                    match message.addr.as_str() {
-                        VOLUME_OSC_ADDR => {
-                            if let Some(OscType::Float(received_volume)) = message.args.first() {
-                                let mut volume = self.volume.lock();
-                                *volume = *received_volume;
-                                received = true;
-                            }
-                        }
-                        VOLUME_DECIBELS_OSC_ADDR => {
-                            if let Some(OscType::String(received_volume_db)) = message.args.first()
-                            {
-                                let mut volume_db = self.volume_db.lock();
-                                *volume_db = Some((*received_volume_db).clone());
-                                received = true;
-                            }
-                        }
-                        DIM_OSC_ADDR => {
-                            if let Some(OscType::Float(received_dim)) = message.args.first() {
-                                let mut dim = self.dim.lock();
-                                *dim = *received_dim;
-                                received = true;
-                            }
-                        }
-                        _ => continue,
+        // Devices other than TotalMix may send their updates as individual messages or in nested
+        // bundles, and with numeric arguments of any type.
+        let mut messages = Vec::new();
+        collect_messages(packet, &mut messages);
+        for message in messages {
+            let (control, arg) = match self.backend.decode(&message) {
+                Some((control, arg)) => (control, number(&arg).map_or(arg, OscType::Float)),
+                None => continue,
+            };
+            match (control, arg) {
+                (Control::Volume, OscType::Float(received_volume)) => {
+                    received = true;
+                    let echo = self.echo(received_volume);
+                    // Echoes of volumes that have since been superseded would only move the
+                    // volume backwards.
+                    if echo == Echo::Superseded {
+                        superseded_volume = true;
+                        continue;
+                    }
+                    updated_volume = Some((received_volume, initialized && echo == Echo::None));
+                }
+                (Control::VolumeDb, OscType::String(volume_db)) => {
+                    received = true;
+                    received_volume_db = Some(volume_db);
+                }
+                (Control::Meter(meter), OscType::Float(value)) => {
+                    received = true;
+                    if self.meters_enabled {
+                        let mut levels = self.levels.lock();
+                        levels.set(meter, value);
+                        updated_levels = Some(*levels);
+                    }
+                }
+                (Control::Mute, OscType::Float(value)) => {
+                    received = true;
+                    self.receive_mute(value.roughly_eq(1.0), initialized);
+                }
+                (Control::Button(button), OscType::Float(value)) => {
+                    received = true;
+                    self.receive_button(button, value, initialized);
+                }
+                (Control::Snapshot(control), arg) => {
+                    received = true;
+                    self.receive_snapshot(control, &arg, initialized);
+                }
+                (Control::Strip(strip), arg) => {
+                    received = true;
+                    self.receive_strip(strip, &arg, initialized);
+                }
+                (Control::Bus(bus), OscType::Float(value)) => {
+                    received = true;
+                    if value.roughly_eq(1.0) {
+                        self.mixer.lock().select_bus(bus);
                     }
                 }
+                _ => (),
+            }
+        }
+
+        // The meters are published once per bundle as they change so frequently.
+        if let Some(levels) = updated_levels {
+            self.publish(StateChange::Levels(levels), false);
+        }
+
+        // Derive the decibel readout from the volume when the device didn't provide it.
+        let volume_db = match (received_volume_db, updated_volume) {
+            (Some(volume_db), _) if !superseded_volume => Some(volume_db),
+            (None, Some((updated_volume, _))) => Some(self.format_volume_db(updated_volume)),
+            _ => None,
+        };
+        self.receive_volume(updated_volume, volume_db);
+
+        if received {
+            {
+                let mut connection = self.connection.lock();
+                connection.last_seen = Some(Instant::now());
+                connection.stale = false;
             }
+            self.refresh_connection_state();
         }
 
         Ok(received)
     }
 
+    /// Increases the volume by the increment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
     pub fn increase_volume(&self) -> Result<bool> {
         self.increase_volume_by_increment(self.increment)
     }
 
+    /// Increases the volume by a multiple of the increment (e.g. while the volume key is held).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn increase_volume_scaled(&self, multiplier: f32) -> Result<bool> {
+        self.increase_volume_by_increment(self.increment.scaled(multiplier))
+    }
+
+    /// Increases the volume by the fine increment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
     pub fn increase_volume_fine(&self) -> Result<bool> {
         self.increase_volume_by_increment(self.fine_increment)
     }
 
+    /// Decreases the volume by the increment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
     pub fn decrease_volume(&self) -> Result<bool> {
         self.decrease_volume_by_increment(self.increment)
     }
 
+    /// Decreases the volume by a multiple of the increment (e.g. while the volume key is held).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn decrease_volume_scaled(&self, multiplier: f32) -> Result<bool> {
+        self.decrease_volume_by_increment(self.increment.scaled(multiplier))
+    }
+
+    /// Decreases the volume by the fine increment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
     pub fn decrease_volume_fine(&self) -> Result<bool> {
         self.decrease_volume_by_increment(self.fine_increment)
     }
 
-    pub fn toggle_dim(&self) -> Result<bool> {
+    /// Sets the volume to the given normalised fader position, which is limited to the range
+    /// between the minimum and maximum volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn set_volume(&self, volume: f32) -> Result<bool> {
         if !self.initialized() {
             return Ok(false);
         }
 
-        let mut dim = self.dim.lock();
-        let new_dim = if (*dim).roughly_eq(1.0) { 0.0 } else { 1.0 };
-        self.send(DIM_OSC_ADDR, 1.0)?;
-        *dim = new_dim;
+        self.ramp_volume(volume, self.ramp_duration, self.ramp_curve)
+    }
 
-        Ok(true)
+    /// Sets the volume to the given level in decibels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn set_volume_db(&self, db: f32) -> Result<bool> {
+        self.set_volume(self.db_to_volume(db))
     }
 
-    fn send(&self, addr: &str, value: f32) -> Result<()> {
-        let sender = match self.sender.as_ref() {
-            Some(sender) => sender,
-            None => return Err(io::Error::from(io::ErrorKind::NotConnected).into()),
-        };
-        let packet = OscPacket::Message(OscMessage {
-            addr: addr.to_string(),
-            args: vec![OscType::Float(value)],
-        });
-        sender.send(&packet)
+    /// Sets the volume to the given percentage of the fader's travel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn set_volume_percent(&self, percent: f32) -> Result<bool> {
+        self.set_volume(percent / 100.0)
     }
 
-    fn increase_volume_by_increment(&self, increment: f32) -> Result<bool> {
+    /// Gradually moves the volume to the given normalised fader position over the given duration,
+    /// taking over from any ramp that is already in progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn ramp_volume(&self, volume: f32, duration: Duration, curve: Curve) -> Result<bool> {
         if !self.initialized() {
             return Ok(false);
         }
 
+        let mut current_volume = self.volume.lock();
+        self.clear_mute();
+        let new_volume = volume.max(self.min_volume).min(self.max_volume);
+        self.move_volume(&mut current_volume, new_volume, duration, curve)
+    }
+
+    /// Gradually fades the volume down to the minimum volume over the given duration (e.g. as a
+    /// sleep timer).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn fade_out(&self, duration: Duration) -> Result<bool> {
+        if !self.initialized() {
+            return Ok(false);
+        }
+
+        let mut volume = self.volume.lock();
+        self.clear_mute();
+        let changed = self.move_volume(&mut volume, self.min_volume, duration, self.ramp_curve)?;
+        let mut schedule = self.schedule.lock();
+        schedule.fading = schedule.ramp.is_some();
+        Ok(changed)
+    }
+
+    /// Stops any ramp in progress, leaving the volume where it currently is.
+    pub fn cancel_ramp(&self) -> bool {
+        self.schedule.lock().ramp.take().is_some()
+    }
+
+    pub fn ramping(&self) -> bool {
+        self.schedule.lock().ramp.is_some()
+    }
+
+    /// Sends the volume that the ramp in progress should currently be at, returning whether the
+    /// ramp is still in progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn tick_ramp(&self) -> Result<bool> {
+        self.tick_ramp_at(Instant::now())
+    }
+
+    fn tick_ramp_at(&self, now: Instant) -> Result<bool> {
         let mut volume = self.volume.lock();
-        let mut new_volume = *volume + increment;
-        if new_volume >= self.max_volume {
-            new_volume = self.max_volume;
+        let new_volume = {
+            let mut schedule = self.schedule.lock();
+            let active_ramp = match schedule.ramp {
+                Some(active_ramp) => active_ramp,
+                None => return Ok(false),
+            };
+            if active_ramp.is_finished(now) {
+                schedule.ramp = None;
+            }
+            active_ramp.volume_at(now, self.backend.as_ref())
+        };
+        self.change_volume(&mut volume, new_volume)?;
+        Ok(self.ramping())
+    }
+
+    /// Sends the latest volume that was held back to respect the max send rate once it is due,
+    /// returning whether it was sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn flush_volume(&self) -> Result<bool> {
+        self.flush_volume_at(Instant::now())
+    }
+
+    fn flush_volume_at(&self, now: Instant) -> Result<bool> {
+        let mut schedule = self.schedule.lock();
+        let volume = match schedule.pending_volume {
+            Some(volume) if schedule.write_due(now, self.send_interval) => volume,
+            _ => return Ok(false),
+        };
+        schedule.pending_volume = None;
+        schedule.last_write = Some(now);
+        drop(schedule);
+
+        self.write_volume(volume, now)?;
+        Ok(true)
+    }
+
+    /// Advances any ramp in progress and sends any volume that was held back at the max send
+    /// rate, waiting while there is nothing to send.
+    pub fn run_scheduler(&self) {
+        loop {
+            {
+                let mut schedule = self.schedule.lock();
+                while schedule.ramp.is_none() && schedule.pending_volume.is_none() {
+                    self.schedule_changed.wait(&mut schedule);
+                }
+            }
+
+            if let Err(error) = self.tick_ramp().and_then(|_| self.flush_volume()) {
+                eprintln!("unable to send the volume: {error}");
+                self.cancel_ramp();
+            }
+            thread::sleep(self.send_interval);
         }
+    }
 
-        if new_volume.roughly_eq(*volume) {
+    /// Mutes the volume, using the mute of the mixer if it has one or otherwise fading the volume
+    /// out so that unmuting can restore it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn mute(&self) -> Result<bool> {
+        if !self.initialized() {
             return Ok(false);
         }
+        if self.supports(Control::Mute) {
+            return self.set_device_muted(true);
+        }
 
-        self.send(VOLUME_OSC_ADDR, new_volume)?;
-        *volume = new_volume;
+        let mut volume = self.volume.lock();
+        {
+            let mut muted_volume = self.muted_volume.lock();
+            if muted_volume.is_some() {
+                return Ok(false);
+            }
+            *muted_volume = Some(self.target_volume(*volume));
+        }
+        self.publish(StateChange::Muted(true), false);
+        self.move_volume(&mut volume, 0.0, self.mute_fade, self.ramp_curve)?;
 
         Ok(true)
     }
 
-    fn decrease_volume_by_increment(&self, increment: f32) -> Result<bool> {
+    /// Unmutes the volume, restoring the volume from before it was muted if the mixer has no mute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn unmute(&self) -> Result<bool> {
         if !self.initialized() {
             return Ok(false);
         }
+        if self.supports(Control::Mute) {
+            return self.set_device_muted(false);
+        }
 
         let mut volume = self.volume.lock();
-        let mut new_volume = *volume - increment;
-        if new_volume < 0.0 {
-            new_volume = 0.0;
+        let muted_volume = match self.clear_mute() {
+            Some(muted_volume) => muted_volume,
+            None => return Ok(false),
+        };
+        self.move_volume(&mut volume, muted_volume, self.mute_fade, self.ramp_curve)?;
+
+        Ok(true)
+    }
+
+    fn set_device_muted(&self, muted: bool) -> Result<bool> {
+        let mut device_muted = self.device_muted.lock();
+        if *device_muted == Some(muted) {
+            return Ok(false);
         }
+        self.send(Control::Mute, if muted { 1.0 } else { 0.0 })?;
+        *device_muted = Some(muted);
+        self.publish(StateChange::Muted(muted), false);
 
-        if new_volume.roughly_eq(*volume) {
+        Ok(true)
+    }
+
+    /// Mutes the volume if it isn't muted and unmutes it otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn toggle_mute(&self) -> Result<bool> {
+        if self.muted() {
+            self.unmute()
+        } else {
+            self.mute()
+        }
+    }
+
+    /// Switches between the listening level and the reference level, remembering the volume of
+    /// the side that is left so that switching back restores it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn toggle_reference(&self) -> Result<bool> {
+        if !self.initialized() {
             return Ok(false);
         }
 
-        self.send(VOLUME_OSC_ADDR, new_volume)?;
-        *volume = new_volume;
+        let mut volume = self.volume.lock();
+        let current_volume = self.unmuted_volume(*volume);
+        let (side, new_volume) = {
+            let mut reference = self.reference.lock();
+            reference.side = match reference.side {
+                ReferenceSide::A => ReferenceSide::B,
+                ReferenceSide::B => ReferenceSide::A,
+            };
+            let new_volume = mem::replace(&mut reference.other_volume, current_volume);
+            (reference.side, new_volume)
+        };
+        self.publish(StateChange::Reference(side), false);
+        self.move_volume(&mut volume, new_volume, self.ramp_duration, self.ramp_curve)?;
 
         Ok(true)
     }
+
+    /// Moves the volume to the level of the preset along with the state of any buttons that it
+    /// includes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no preset with the name or a message couldn't be sent to the
+    /// device.
+    pub fn recall_preset(&self, name: &str) -> Result<bool> {
+        let preset = self.preset(name)?;
+        if !self.initialized() {
+            return Ok(false);
+        }
+
+        let volume = match preset.target_volume(|db| self.db_to_volume(db)) {
+            Some(volume) => volume,
+            None => return Ok(false),
+        };
+        let duration = preset
+            .ramp_time
+            .map_or(self.ramp_duration, Duration::from_secs_f64);
+        let mut changed = self.ramp_volume(volume, duration, self.ramp_curve)?;
+        if let Some(dimmed) = preset.dim {
+            changed |= self.set_dim(dimmed)?;
+        }
+        if let Some(mono) = preset.mono {
+            changed |= self.set_button(Button::Mono, mono)?;
+        }
+        self.publish(StateChange::PresetRecalled(preset.name.clone()), false);
+
+        Ok(changed)
+    }
+
+    /// Recalls one of the mixer snapshots stored in `TotalMix`, numbered from 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn recall_snapshot(&self, snapshot: usize) -> Result<bool> {
+        snapshots::check_snapshot(snapshot)?;
+        if !self.initialized()
+            || !self.supports(Control::Snapshot(SnapshotControl::Recall(snapshot)))
+        {
+            return Ok(false);
+        }
+
+        self.send(Control::Snapshot(SnapshotControl::Recall(snapshot)), 1.0)?;
+        let name = {
+            let mut snapshots = self.snapshots.lock();
+            if snapshots.active() != Some(snapshot) {
+                snapshots.request(snapshot);
+            }
+            snapshots.name(snapshot).map(ToString::to_string)
+        };
+        self.publish(StateChange::SnapshotRecalled(snapshot, name), false);
+
+        Ok(true)
+    }
+
+    // The volume and its readout are updated together once the whole packet has been processed so
+    // that they are never seen half-updated.
+    fn receive_volume(&self, updated_volume: Option<(f32, bool)>, new_volume_db: Option<String>) {
+        let mut volume = self.volume.lock();
+        let mut changed_volume = None;
+        if let Some((updated_volume, external)) = updated_volume {
+            if volume.roughly_ne(updated_volume) {
+                // Turning the volume on the device takes over from a ramp and from muting.
+                if external {
+                    self.cancel_ramp();
+                    self.clear_mute();
+                }
+                changed_volume = Some(external);
+            }
+            *volume = updated_volume;
+        }
+
+        let mut volume_db = self.volume_db.lock();
+        let changed_volume_db = new_volume_db.is_some() && *volume_db != new_volume_db;
+        if changed_volume_db {
+            *volume_db = new_volume_db;
+        }
+        match changed_volume {
+            Some(external) => {
+                self.publish(StateChange::Volume(*volume, volume_db.clone()), external);
+            }
+            None if changed_volume_db => {
+                self.publish(StateChange::VolumeDb(volume_db.clone()), false);
+            }
+            None => (),
+        }
+    }
+
+    // Records the state of a control room button.
+    fn receive_button(&self, button: Button, value: f32, initialized: bool) {
+        let (changed, pending) = self
+            .buttons
+            .lock()
+            .entry(button)
+            .or_default()
+            .receive(value);
+        if changed {
+            self.publish(
+                StateChange::Button(button, value.roughly_eq(1.0)),
+                initialized && !pending,
+            );
+        }
+    }
+
+    // Records the state of the mute of mixers that have one.
+    fn receive_mute(&self, muted: bool, initialized: bool) {
+        let mut device_muted = self.device_muted.lock();
+        if *device_muted != Some(muted) {
+            *device_muted = Some(muted);
+            self.publish(StateChange::Muted(muted), initialized);
+        }
+    }
+
+    // Records the state of the snapshots.
+    fn receive_snapshot(&self, control: SnapshotControl, arg: &OscType, initialized: bool) {
+        let mut snapshots = self.snapshots.lock();
+        match snapshots.receive(control, arg) {
+            // Snapshots recalled here have already been published.
+            Some(SnapshotChange::Recalled(snapshot)) if !snapshots.take_requested(snapshot) => {
+                let name = snapshots.name(snapshot).map(ToString::to_string);
+                self.publish(StateChange::SnapshotRecalled(snapshot, name), initialized);
+            }
+            Some(SnapshotChange::Named(snapshot, name)) => {
+                self.publish(StateChange::SnapshotNamed(snapshot, name), false);
+            }
+            _ => (),
+        }
+    }
+
+    /// Presses the dim button.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn toggle_dim(&self) -> Result<bool> {
+        self.toggle_button(Button::Dim)
+    }
+
+    /// Dims or undims the volume, pressing the dim button as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn set_dim(&self, dimmed: bool) -> Result<bool> {
+        self.set_button(Button::Dim, dimmed)
+    }
+
+    /// Presses the button to change it from the state it is expected to be in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn toggle_button(&self, button: Button) -> Result<bool> {
+        let active = self
+            .buttons
+            .lock()
+            .get(&button)
+            .map_or(false, ButtonState::expected_active);
+        self.set_button(button, !active)
+    }
+
+    /// Presses the button if the device isn't already in (or about to be in) the given state.  The
+    /// new state is only reflected once the device reports it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn set_button(&self, button: Button, active: bool) -> Result<bool> {
+        if !self.initialized() || !self.supports(Control::Button(button)) {
+            return Ok(false);
+        }
+
+        let mut buttons = self.buttons.lock();
+        if !buttons
+            .entry(button)
+            .or_default()
+            .press(active, Instant::now())
+        {
+            return Ok(false);
+        }
+        self.send(Control::Button(button), 1.0)?;
+
+        Ok(true)
+    }
+
+    /// Verifies the presses of buttons once the device has had time to respond, pressing them
+    /// again or requesting their state again as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn verify_buttons(&self) -> Result<()> {
+        self.verify_buttons_at(Instant::now())
+    }
+
+    fn verify_buttons_at(&self, now: Instant) -> Result<()> {
+        let mut buttons = self.buttons.lock();
+        for (button, state) in buttons.iter_mut() {
+            if let Some(value) = state.verify(now) {
+                self.send(Control::Button(*button), value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Records the state of a strip on page 1.
+    fn receive_strip(&self, strip: Strip, arg: &OscType, initialized: bool) {
+        let change = self.mixer.lock().receive(strip, arg);
+        if let Some((channel_id, channel)) = change {
+            self.publish(StateChange::Channel(channel_id, channel), initialized);
+        }
+    }
+
+    /// Shows the bus on page 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    pub fn select_bus(&self, bus: Bus) -> Result<()> {
+        self.send(Control::Bus(bus), 1.0)?;
+        self.mixer.lock().select_bus(bus);
+        Ok(())
+    }
+
+    /// Shows the bank of channels starting after the given number of channels on page 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message couldn't be sent to the device.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn select_bank(&self, bank_start: usize) -> Result<()> {
+        self.send(Control::BankStart, bank_start as f32)?;
+        self.mixer.lock().select_bank(bank_start);
+        Ok(())
+    }
+
+    /// Sets the volume of the channel to the given normalised fader position, showing the channel
+    /// on page 1 first if it isn't already shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn set_channel_volume(&self, channel: ChannelId, volume: f32) -> Result<bool> {
+        if !self.channels_initialized() {
+            return Ok(false);
+        }
+
+        let volume = volume.clamp(0.0, 1.0);
+        let strip = self.show_channel(channel)?;
+        self.send(Control::Strip(Strip::Volume(strip)), volume)?;
+        self.mixer.lock().set_volume(channel, volume);
+
+        Ok(true)
+    }
+
+    /// Sets the volume of the channel to the given level in decibels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn set_channel_volume_db(&self, channel: ChannelId, db: f32) -> Result<bool> {
+        self.set_channel_volume(channel, self.db_to_volume(db))
+    }
+
+    /// Increases the volume of the channel by the increment.  The volume of a channel is only
+    /// known once the channel has been shown, so the first press may only show the channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn increase_channel_volume(&self, channel: ChannelId) -> Result<bool> {
+        let volume = match self.known_channel_volume(channel)? {
+            Some(volume) => volume,
+            None => return Ok(false),
+        };
+        self.set_channel_volume(
+            channel,
+            self.increment.increase(volume, self.backend.as_ref()),
+        )
+    }
+
+    /// Decreases the volume of the channel by the increment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn decrease_channel_volume(&self, channel: ChannelId) -> Result<bool> {
+        let volume = match self.known_channel_volume(channel)? {
+            Some(volume) => volume,
+            None => return Ok(false),
+        };
+        self.set_channel_volume(
+            channel,
+            self.increment.decrease(volume, self.backend.as_ref()),
+        )
+    }
+
+    /// Presses the mute button of the channel, which is only reflected once the device reports
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn toggle_channel_mute(&self, channel: ChannelId) -> Result<bool> {
+        self.press_strip(channel, Strip::Mute)
+    }
+
+    /// Presses the solo button of the channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel number is out of range or a message couldn't be sent to the
+    /// device.
+    pub fn toggle_channel_solo(&self, channel: ChannelId) -> Result<bool> {
+        self.press_strip(channel, Strip::Solo)
+    }
+
+    // Selects the bus and bank that the channel belongs to, returning the strip it is shown on.
+    // These are selected before every write since scrolling through the banks in `TotalMix` itself
+    // isn't reported, so the bank last selected here may no longer be shown.
+    fn show_channel(&self, channel: ChannelId) -> Result<usize> {
+        if !channel.is_valid() {
+            return Err(ChannelNumberRangeError.into());
+        }
+        let bank_start = self.mixer.lock().bank_start_of(channel);
+        self.select_bus(channel.bus)?;
+        self.select_bank(bank_start)?;
+        Ok(channel.number - bank_start)
+    }
+
+    // Channels are reached by showing them on page 1, which only some mixers have.
+    fn channels_initialized(&self) -> bool {
+        self.initialized() && self.supports(Control::BankStart)
+    }
+
+    fn known_channel_volume(&self, channel: ChannelId) -> Result<Option<f32>> {
+        if !self.channels_initialized() {
+            return Ok(None);
+        }
+
+        let volume = self
+            .channel(channel)
+            .map(|channel| channel.volume)
+            .filter(|volume| (*volume).roughly_ne(-1.0));
+        if volume.is_none() {
+            self.show_channel(channel)?;
+        }
+        Ok(volume)
+    }
+
+    fn press_strip(&self, channel: ChannelId, control: fn(usize) -> Strip) -> Result<bool> {
+        if !self.channels_initialized() {
+            return Ok(false);
+        }
+
+        let strip = self.show_channel(channel)?;
+        self.send(Control::Strip(control(strip)), 1.0)?;
+
+        Ok(true)
+    }
+
+    fn publish(&self, change: StateChange, external: bool) {
+        // Subscribers that have gone away are dropped.
+        let notification = Notification { change, external };
+        self.subscribers
+            .lock()
+            .retain(|subscriber| subscriber.send(notification.clone()).is_ok());
+    }
+
+    fn format_volume_db(&self, volume: f32) -> String {
+        fader::format_decibels(
+            self.backend.volume_to_db(volume),
+            self.db_precision,
+            &self.db_negative_infinity,
+        )
+    }
+
+    fn echo(&self, received_volume: f32) -> Echo {
+        let mut sent_volumes = self.sent_volumes.lock();
+        while sent_volumes
+            .front()
+            .map_or(false, |(_, sent_time)| sent_time.elapsed() >= ECHO_WINDOW)
+        {
+            sent_volumes.pop_front();
+        }
+
+        let pending = self.schedule.lock().pending_volume.is_some();
+        match sent_volumes
+            .iter()
+            .rposition(|(sent_volume, _)| (received_volume - sent_volume).abs() < ECHO_TOLERANCE)
+        {
+            None => Echo::None,
+            Some(index) if index + 1 == sent_volumes.len() && !pending => Echo::Latest,
+            Some(_) => Echo::Superseded,
+        }
+    }
+
+    // Volume writes are sent straight away unless one was sent too recently, in which case the
+    // latest volume is held back until the scheduler sends it.
+    fn send_volume(&self, volume: f32) -> Result<()> {
+        let mut schedule = self.schedule.lock();
+        let now = Instant::now();
+        if !schedule.write_due(now, self.send_interval) {
+            schedule.pending_volume = Some(volume);
+            self.schedule_changed.notify_all();
+            return Ok(());
+        }
+        schedule.pending_volume = None;
+        schedule.last_write = Some(now);
+        drop(schedule);
+
+        self.write_volume(volume, now)
+    }
+
+    fn write_volume(&self, volume: f32, now: Instant) -> Result<()> {
+        self.send(Control::Volume, volume)?;
+        self.sent_volumes.lock().push_back((volume, now));
+        Ok(())
+    }
+
+    // Controls that the mixer lacks are ignored.
+    fn send(&self, control: Control, value: f32) -> Result<()> {
+        let message = match self.backend.encode(control, value) {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        self.send_message(message)
+    }
+
+    fn send_message(&self, message: OscMessage) -> Result<()> {
+        let sender = match self.sender.as_ref() {
+            Some(sender) => sender,
+            None => return Err(io::Error::from(io::ErrorKind::NotConnected).into()),
+        };
+        sender.send(&OscPacket::Message(message))
+    }
+
+    fn increase_volume_by_increment(&self, increment: Increment) -> Result<bool> {
+        if !self.initialized() {
+            return Ok(false);
+        }
+
+        let mut volume = self.volume.lock();
+        let new_volume = increment
+            .increase(self.unmuted_volume(*volume), self.backend.as_ref())
+            .max(self.min_volume)
+            .min(self.max_volume);
+        self.move_volume(&mut volume, new_volume, self.ramp_duration, self.ramp_curve)
+    }
+
+    fn decrease_volume_by_increment(&self, increment: Increment) -> Result<bool> {
+        if !self.initialized() {
+            return Ok(false);
+        }
+
+        // The volume is never decreased below the minimum, unless it was already set lower on
+        // the device itself.
+        let mut volume = self.volume.lock();
+        let target_volume = self.unmuted_volume(*volume);
+        let new_volume = increment
+            .decrease(target_volume, self.backend.as_ref())
+            .max(self.min_volume.min(target_volume));
+        self.move_volume(&mut volume, new_volume, self.ramp_duration, self.ramp_curve)
+    }
+
+    // Stepping the volume while muted unmutes and steps from the volume before muting.
+    fn unmuted_volume(&self, volume: f32) -> f32 {
+        self.clear_mute()
+            .unwrap_or_else(|| self.target_volume(volume))
+    }
+
+    fn clear_mute(&self) -> Option<f32> {
+        let muted_volume = self.muted_volume.lock().take();
+        if muted_volume.is_some() {
+            self.publish(StateChange::Muted(false), false);
+        }
+        muted_volume
+    }
+
+    // Repeated changes while a step is ramping build upon where the ramp is heading, whereas a
+    // fade is stopped so that they start from the current level.
+    fn target_volume(&self, volume: f32) -> f32 {
+        let mut schedule = self.schedule.lock();
+        if schedule.fading {
+            schedule.ramp = None;
+            schedule.fading = false;
+        }
+        schedule.ramp.map_or(volume, |ramp| ramp.target())
+    }
+
+    fn move_volume(
+        &self,
+        volume: &mut f32,
+        new_volume: f32,
+        duration: Duration,
+        curve: Curve,
+    ) -> Result<bool> {
+        let mut schedule = self.schedule.lock();
+        schedule.fading = false;
+        if duration.is_zero() {
+            schedule.ramp = None;
+            drop(schedule);
+            return self.change_volume(volume, new_volume);
+        }
+
+        if new_volume.roughly_eq(schedule.ramp.map_or(*volume, |ramp| ramp.target())) {
+            return Ok(false);
+        }
+
+        schedule.ramp = Some(Ramp::new(
+            *volume,
+            new_volume,
+            duration,
+            curve,
+            Instant::now(),
+        ));
+        self.schedule_changed.notify_all();
+        Ok(true)
+    }
+
+    fn change_volume(&self, volume: &mut f32, new_volume: f32) -> Result<bool> {
+        if new_volume.roughly_eq(*volume) {
+            return Ok(false);
+        }
+
+        self.send_volume(new_volume)?;
+        *volume = new_volume;
+        let volume_db = self.format_volume_db(new_volume);
+        *self.volume_db.lock() = Some(volume_db.clone());
+        self.publish(StateChange::Volume(new_volume, Some(volume_db)), false);
+
+        Ok(true)
+    }
+}
+
+fn collect_messages(packet: OscPacket, messages: &mut Vec<OscMessage>) {
+    match packet {
+        OscPacket::Message(message) => messages.push(message),
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                collect_messages(packet, messages);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rosc::OscBundle;
+
+    use super::*;
+    use crate::{
+        meters::Level,
+        testing::{assert_close, connect},
+    };
+
+    fn message(addr: &str, arg: OscType) -> OscPacket {
+        OscPacket::Message(OscMessage {
+            addr: addr.to_string(),
+            args: vec![arg],
+        })
+    }
+
+    fn bundle(content: Vec<OscPacket>) -> OscPacket {
+        OscPacket::Bundle(OscBundle {
+            timetag: (0, 1).into(),
+            content,
+        })
+    }
+
+    // A time by which any ramp or pending press started by a test is over.
+    fn later() -> Instant {
+        Instant::now() + Duration::from_secs(10)
+    }
+
+    #[test]
+    fn request_volume() {
+        let (_simulator, manager) = connect();
+        assert!(manager.initialized());
+        assert!(manager.volume().roughly_eq(0.5));
+        assert_eq!(manager.volume_db().unwrap(), "-14.6 dB");
+        assert!(!manager.dimmed());
+    }
+
+    #[test]
+    fn increase_volume() {
+        let (simulator, manager) = connect();
+        assert!(manager.increase_volume().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.52));
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.52));
+    }
+
+    #[test]
+    fn volume_db_updated_immediately() {
+        let (_simulator, manager) = connect();
+        assert!(manager.increase_volume().unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-13.4 dB");
+    }
+
+    #[test]
+    fn volume_db_derived_when_not_received() {
+        let (simulator, mut manager) = connect();
+        manager.set_db_precision(2).unwrap();
+        manager.set_db_negative_infinity("-inf");
+        simulator.set_send_volume_db(false);
+        simulator.set_volume(0.25).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-35.15 dB");
+        simulator.set_volume(0.0).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-inf");
+    }
+
+    #[test]
+    fn increase_volume_db() {
+        let (simulator, mut manager) = connect();
+        manager.set_increment_db(2.0).unwrap();
+        assert!(manager.increase_volume().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert_close(
+            fader::volume_to_db(simulator.volume()) - fader::volume_to_db(0.5),
+            2.0,
+        );
+    }
+
+    #[test]
+    fn decrease_volume_to_min_volume() {
+        let (_simulator, mut manager) = connect();
+        manager.set_min_volume(0.45).unwrap();
+        assert!(manager.decrease_volume().unwrap());
+        assert!(manager.decrease_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.46));
+        assert!(manager.decrease_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.45));
+        assert!(!manager.decrease_volume().unwrap());
+    }
+
+    #[test]
+    fn set_volume() {
+        let (simulator, mut manager) = connect();
+        manager.set_max_volume(0.9).unwrap();
+        manager.set_min_volume(0.1).unwrap();
+        assert!(manager.set_volume(0.75).unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.75));
+        assert!(!manager.set_volume(0.75).unwrap());
+        assert!(manager.set_volume(1.0).unwrap());
+        assert!(manager.volume().roughly_eq(0.9));
+        assert!(manager.set_volume(0.0).unwrap());
+        assert!(manager.volume().roughly_eq(0.1));
+    }
+
+    #[test]
+    fn set_volume_db_and_percent() {
+        let (_simulator, manager) = connect();
+        assert!(manager.set_volume_db(-20.0).unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-20.0 dB");
+        assert!(manager.set_volume_percent(25.0).unwrap());
+        assert!(manager.volume().roughly_eq(0.25));
+    }
+
+    #[test]
+    fn ramp_volume() {
+        let (simulator, mut manager) = connect();
+        manager.send_interval = Duration::ZERO;
+        assert!(manager
+            .ramp_volume(0.7, Duration::from_millis(50), Curve::Linear)
+            .unwrap());
+        assert!(manager.volume().roughly_eq(0.5));
+        assert!(!manager.tick_ramp_at(later()).unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(manager.volume().roughly_eq(0.7));
+        assert!(simulator.volume().roughly_eq(0.7));
+    }
+
+    #[test]
+    fn ramp_retargeted_and_cancelled() {
+        let (_simulator, mut manager) = connect();
+        manager.set_ramp_duration(Duration::from_secs(1)).unwrap();
+        assert!(manager.set_volume(0.7).unwrap());
+        assert!(manager.increase_volume().unwrap());
+        assert!(manager.tick_ramp().unwrap());
+        assert!(manager.volume() < 0.6);
+
+        // Moving the volume instantly takes over from the ramp.
+        assert!(manager
+            .ramp_volume(0.3, Duration::ZERO, Curve::Linear)
+            .unwrap());
+        assert!(!manager.ramping());
+        assert!(manager.volume().roughly_eq(0.3));
+    }
+
+    #[test]
+    fn steps_take_over_from_fade_out() {
+        let (_simulator, mut manager) = connect();
+        manager.send_interval = Duration::ZERO;
+        assert!(manager.fade_out(Duration::from_secs(10)).unwrap());
+        assert!(manager
+            .tick_ramp_at(Instant::now() + Duration::from_secs(5))
+            .unwrap());
+        let faded = manager.volume();
+        assert!(faded < 0.5);
+        assert!(manager.increase_volume().unwrap());
+        assert!(!manager.ramping());
+        assert!(manager.volume().roughly_eq(faded + 0.02));
+
+        // Muting remembers the level the fade had reached rather than where it was heading.
+        let faded = manager.volume();
+        assert!(manager.fade_out(Duration::from_secs(10)).unwrap());
+        assert!(manager.mute().unwrap());
+        assert!(manager.unmute().unwrap());
+        assert!(manager.volume().roughly_eq(faded));
+    }
+
+    #[test]
+    fn ramp_cancelled_by_device_volume_change() {
+        let (simulator, manager) = connect();
+        assert!(manager
+            .ramp_volume(0.7, Duration::from_secs(1), Curve::LinearDb)
+            .unwrap());
+        simulator.set_volume(0.25).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(!manager.ramping());
+        assert!(manager.volume().roughly_eq(0.25));
+    }
+
+    #[test]
+    fn toggle_dim() {
+        let (simulator, manager) = connect();
+        assert!(manager.toggle_dim().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.dimmed());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.dimmed());
+    }
+
+    #[test]
+    fn toggle_button() {
+        let (simulator, manager) = connect();
+        assert!(!manager.button_active(Button::Mono));
+        assert!(manager.toggle_button(Button::Mono).unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.button_active(Button::Mono));
+        assert!(!simulator.dimmed());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.button_active(Button::Mono));
+        assert!(!manager.dimmed());
+
+        assert!(!manager.set_button(Button::Mono, true).unwrap());
+        assert!(manager.set_button(Button::Mono, false).unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(!simulator.button_active(Button::Mono));
+    }
+
+    #[test]
+    fn device_button_change() {
+        let (simulator, manager) = connect();
+        let changes = manager.subscribe();
+        simulator.set_button(Button::Talkback, true).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.button_active(Button::Talkback));
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            Notification {
+                change: StateChange::Button(Button::Talkback, true),
+                external: true
+            }
+        );
+    }
+
+    #[test]
+    fn set_dim_only_presses_when_needed() {
+        let (simulator, manager) = connect();
+        assert!(!manager.set_dim(false).unwrap());
+        assert!(manager.set_dim(true).unwrap());
+        assert!(!manager.set_dim(true).unwrap());
+        assert!(!manager.dimmed());
+        assert!(simulator.handle_next().unwrap());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.dimmed());
+        assert!(!manager.set_dim(true).unwrap());
+    }
+
+    #[test]
+    fn set_dim_corrects_stale_state() {
+        let (simulator, manager) = connect();
+
+        // The device was dimmed without the manager hearing about it, so pressing the button
+        // undims it instead.
+        simulator.set_dimmed(true).unwrap();
+        assert!(manager.set_dim(true).unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(!simulator.dimmed());
+        while manager.recieve_volume().unwrap() {}
+        assert!(!manager.dimmed());
+
+        manager.verify_buttons_at(later()).unwrap();
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.dimmed());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.dimmed());
+        assert!(!manager.set_dim(true).unwrap());
+    }
+
+    #[test]
+    fn mute_and_unmute() {
+        let (simulator, mut manager) = connect();
+        manager.send_interval = Duration::ZERO;
+        let changes = manager.subscribe();
+        assert!(manager.mute().unwrap());
+        assert!(manager.muted());
+        assert!(!manager.mute().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.0));
+        assert_eq!(changes.try_recv().unwrap().change, StateChange::Muted(true));
+
+        assert!(manager.toggle_mute().unwrap());
+        assert!(!manager.muted());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.5));
+        assert!(changes
+            .try_iter()
+            .any(|notification| notification.change == StateChange::Muted(false)));
+    }
+
+    #[test]
+    fn volume_change_unmutes() {
+        let (_simulator, mut manager) = connect();
+        manager.send_interval = Duration::ZERO;
+        assert!(manager.mute().unwrap());
+        assert!(manager.increase_volume().unwrap());
+        assert!(!manager.muted());
+        assert!(manager.volume().roughly_eq(0.52));
+    }
+
+    #[test]
+    fn mute_fades_volume() {
+        let (_simulator, mut manager) = connect();
+        manager.set_mute_fade(Duration::from_millis(40)).unwrap();
+        assert!(manager.mute().unwrap());
+        assert!(manager.ramping());
+        assert!(manager.volume().roughly_eq(0.5));
+        assert!(!manager.tick_ramp_at(later()).unwrap());
+        assert!(manager.volume().roughly_eq(0.0));
+        assert!(manager.muted());
+    }
+
+    #[test]
+    fn publishes_state_changes() {
+        let (simulator, manager) = connect();
+        let changes = manager.subscribe();
+        simulator.set_volume(0.25).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            Notification {
+                change: StateChange::Volume(0.25, Some("-35.2 dB".to_string())),
+                external: true
+            }
+        );
+        assert!(changes.try_recv().is_err());
+
+        assert!(manager.toggle_dim().unwrap());
+        assert!(changes.try_recv().is_err());
+        assert!(simulator.handle_next().unwrap());
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            Notification {
+                change: StateChange::Button(Button::Dim, true),
+                external: false
+            }
+        );
+    }
+
+    #[test]
+    fn skips_undecodable_packets() {
+        let (simulator, manager) = connect();
+        simulator.send_bytes(b"not osc").unwrap();
+        assert!(!manager.recieve_volume().unwrap());
+
+        simulator
+            .send_packet(&message("/1/mastervolume", OscType::Float(0.25)))
+            .unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.25));
+    }
+
+    #[test]
+    fn receives_nested_bundles_and_other_numeric_types() {
+        let (simulator, manager) = connect();
+        let changes = manager.subscribe();
+        simulator
+            .send_packet(&bundle(vec![
+                message("/1/mastervolume", OscType::Double(0.25)),
+                bundle(vec![
+                    message(
+                        "/1/mastervolumeVal",
+                        OscType::String("-35.2 dB".to_string()),
+                    ),
+                    bundle(vec![message("/1/mainDim", OscType::Int(1))]),
+                ]),
+            ]))
+            .unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.25));
+        assert!(manager.dimmed());
+
+        // The volume is published along with the readout from the nested bundle.
+        let changes: Vec<_> = changes.try_iter().map(|change| change.change).collect();
+        assert!(changes.contains(&StateChange::Volume(0.25, Some("-35.2 dB".to_string()))));
+        assert!(!changes
+            .iter()
+            .any(|change| matches!(change, StateChange::VolumeDb(_))));
+
+        simulator
+            .send_packet(&message("/1/mastervolume", OscType::Float(0.5)))
+            .unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-14.6 dB");
+    }
+
+    #[test]
+    fn ignores_echoes_of_own_changes() {
+        let (simulator, manager) = connect();
+        let changes = manager.subscribe();
+        assert!(manager.increase_volume().unwrap());
+        assert!(!changes.try_recv().unwrap().external);
+
+        assert!(simulator.handle_next().unwrap());
+        assert!(manager.recieve_volume().unwrap());
+        assert!(changes
+            .try_iter()
+            .all(|notification| !notification.external));
+
+        // `TotalMix` may echo a slightly different value due to the resolution of its faders.
+        simulator.set_volume(0.521).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(
+            changes.try_recv().unwrap(),
+            Notification {
+                change: StateChange::Volume(0.521, manager.volume_db()),
+                external: false
+            }
+        );
+    }
+
+    #[test]
+    fn coalesces_rapid_changes() {
+        let (simulator, mut manager) = connect();
+        manager.set_max_send_rate(10.0).unwrap();
+        let changes = manager.subscribe();
+        assert!(manager.increase_volume().unwrap());
+        assert!(manager.increase_volume().unwrap());
+        assert!(manager.increase_volume().unwrap());
+        assert!(!manager.flush_volume().unwrap());
+
+        // The echo of the first change must not move the volume back.
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.52));
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.56));
+        assert_eq!(manager.volume_db().unwrap(), "-11.2 dB");
+
+        assert!(manager
+            .flush_volume_at(Instant::now() + Duration::from_millis(100))
+            .unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.56));
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.56));
+        assert!(changes
+            .try_iter()
+            .all(|notification| !notification.external));
+    }
+
+    #[test]
+    fn channel_volume() {
+        let (simulator, manager) = connect();
+        let phones = ChannelId {
+            bus: Bus::Output,
+            number: 11,
+        };
+        // The bus and the bank that the channel belongs to are selected before its volume is set.
+        assert!(manager.set_channel_volume_db(phones, 0.0).unwrap());
+        for _ in 0..3 {
+            assert!(simulator.handle_next().unwrap());
+        }
+        assert!(simulator
+            .channel_volume(phones)
+            .roughly_eq(fader::db_to_volume(0.0)));
+        for _ in 0..3 {
+            assert!(manager.recieve_volume().unwrap());
+        }
+        assert_eq!(manager.bus(), Some(Bus::Output));
+        assert_eq!(manager.bank_start(), Some(8));
+        let channel = manager.channel(phones).unwrap();
+        assert_eq!(channel.name.as_deref(), Some("Output 11"));
+        assert_eq!(channel.volume_db.as_deref(), Some("0.0 dB"));
+
+        // Scrolling through the banks in TotalMix doesn't send the bank start, so it's selected
+        // again before the next write rather than writing to whichever channel is now shown.
+        simulator.set_bank_start(0).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.set_channel_volume(phones, 0.25).unwrap());
+        for _ in 0..3 {
+            assert!(simulator.handle_next().unwrap());
+        }
+        assert!(simulator.channel_volume(phones).roughly_eq(0.25));
+        let playback = ChannelId {
+            bus: Bus::Output,
+            number: 3,
+        };
+        assert!(simulator.channel_volume(playback).roughly_ne(0.25));
+    }
+
+    #[test]
+    fn add_channel() {
+        let (_simulator, mut manager) = connect();
+        let channel = |number| ChannelId {
+            bus: Bus::Input,
+            number,
+        };
+        assert!(manager.add_channel("Mic", channel(0)).is_err());
+        assert!(manager.add_channel("Mic", channel(199)).is_err());
+        manager.add_channel("Mic", channel(198)).unwrap();
+        assert_eq!(manager.channel_id("Mic").unwrap(), channel(198));
+    }
+
+    #[test]
+    fn toggle_channel_mute() {
+        let (simulator, manager) = connect();
+        let channel = ChannelId {
+            bus: Bus::Input,
+            number: 2,
+        };
+        assert!(manager.toggle_channel_mute(channel).unwrap());
+        for _ in 0..3 {
+            assert!(simulator.handle_next().unwrap());
+        }
+        assert!(simulator.channel_muted(channel));
+        for _ in 0..3 {
+            assert!(manager.recieve_volume().unwrap());
+        }
+        assert!(manager.channel(channel).unwrap().muted);
+    }
+
+    #[test]
+    fn recall_preset() {
+        let (simulator, mut manager) = connect();
+        let preset = Preset {
+            name: "Mix Reference".to_string(),
+            volume: None,
+            volume_db: Some(-18.0),
+            dim: Some(true),
+            mono: Some(false),
+            ramp_time: Some(0.0),
+        };
+        manager.add_preset(preset.clone()).unwrap();
+        assert!(manager.add_preset(preset).is_err());
+        let changes = manager.subscribe();
+
+        assert!(manager.recall_preset("Mix Reference").unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(fader::db_to_volume(-18.0)));
+        assert!(simulator.dimmed());
+        assert!(!simulator.button_active(Button::Mono));
+        assert_eq!(
+            changes.try_iter().last().unwrap().change,
+            StateChange::PresetRecalled("Mix Reference".to_string())
+        );
+        assert!(manager.recall_preset("Client Playback").is_err());
+    }
+
+    #[test]
+    fn recall_snapshot() {
+        let (simulator, manager) = connect();
+        simulator.set_snapshot_name(2, "Tracking").unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.snapshot_name(2).as_deref(), Some("Tracking"));
+        let changes = manager.subscribe();
+
+        assert!(manager.recall_snapshot(2).unwrap());
+        assert_eq!(
+            changes.try_recv().unwrap().change,
+            StateChange::SnapshotRecalled(2, Some("Tracking".to_string()))
+        );
+        assert!(simulator.handle_next().unwrap());
+        assert_eq!(simulator.active_snapshot(), Some(2));
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.active_snapshot(), Some(2));
+        assert!(changes.try_recv().is_err());
+        assert!(manager.recall_snapshot(9).is_err());
+    }
+
+    #[test]
+    fn device_snapshot_change() {
+        let (simulator, manager) = connect();
+        let changes = manager.subscribe();
+        simulator.recall_snapshot(5).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.active_snapshot(), Some(5));
+        assert!(changes.try_iter().any(|notification| notification
+            == Notification {
+                change: StateChange::SnapshotRecalled(5, Some("Snapshot 5".to_string())),
+                external: true
+            }));
+    }
+
+    #[test]
+    fn toggle_reference() {
+        let (simulator, mut manager) = connect();
+        let reference_volume = fader::db_to_volume(-18.0);
+        manager.set_reference_volume(reference_volume).unwrap();
+        manager.send_interval = Duration::ZERO;
+        let changes = manager.subscribe();
+
+        assert!(manager.toggle_reference().unwrap());
+        assert_eq!(manager.reference_side(), ReferenceSide::B);
+        assert_eq!(
+            changes.try_recv().unwrap().change,
+            StateChange::Reference(ReferenceSide::B)
+        );
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(reference_volume));
+
+        // Adjusting the reference level is remembered when switching back to the listening level.
+        assert!(manager.increase_volume().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(manager.toggle_reference().unwrap());
+        assert_eq!(manager.reference_side(), ReferenceSide::A);
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(0.5));
+
+        assert!(manager.toggle_reference().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.volume().roughly_eq(reference_volume + 0.02));
+    }
+
+    #[test]
+    fn receive_levels() {
+        let (simulator, mut manager) = connect();
+        let changes = manager.subscribe();
+        let levels = Levels {
+            left: Level {
+                rms: 0.4,
+                peak: 0.6,
+            },
+            right: Level {
+                rms: 0.3,
+                peak: 0.5,
+            },
+        };
+
+        // The meters are ignored unless they're enabled.
+        simulator.send_levels(&levels).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.levels(), Levels::default());
+        assert!(changes.try_recv().is_err());
+
+        manager.set_meters_enabled(true);
+        simulator.send_levels(&levels).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert_eq!(manager.levels(), levels);
+        assert_eq!(
+            changes.try_recv().unwrap().change,
+            StateChange::Levels(levels)
+        );
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn device_volume_change() {
+        let (simulator, manager) = connect();
+        simulator.set_volume(0.25).unwrap();
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.volume().roughly_eq(0.25));
+    }
 }
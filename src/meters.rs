@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+/// The meters of the main output, which `TotalMix` sends as normalised values between 0.0 and 1.0
+/// while sending level data is enabled in its OSC settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Meter {
+    LevelLeft,
+    LevelRight,
+    PeakLeft,
+    PeakRight,
+}
+
+impl Meter {
+    pub const ALL: [Self; 4] = [
+        Self::LevelLeft,
+        Self::LevelRight,
+        Self::PeakLeft,
+        Self::PeakRight,
+    ];
+}
+
+/// The level of one side of the main output.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Level {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// The stereo level of the main output.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Levels {
+    pub left: Level,
+    pub right: Level,
+}
+
+impl Levels {
+    /// Records a value received from the device for the meter.
+    pub const fn set(&mut self, meter: Meter, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match meter {
+            Meter::LevelLeft => self.left.rms = value,
+            Meter::LevelRight => self.right.rms = value,
+            Meter::PeakLeft => self.left.peak = value,
+            Meter::PeakRight => self.right.peak = value,
+        }
+    }
+
+    /// The values that the device sends for each meter.
+    #[must_use]
+    pub const fn values(&self) -> [(Meter, f32); 4] {
+        [
+            (Meter::LevelLeft, self.left.rms),
+            (Meter::LevelRight, self.right.rms),
+            (Meter::PeakLeft, self.left.peak),
+            (Meter::PeakRight, self.right.peak),
+        ]
+    }
+}
+
+/// Holds the highest recent peak for long enough to be read, after which it falls back to the
+/// current peak.
+#[derive(Debug, Clone, Copy)]
+pub struct PeakHold {
+    hold_time: Duration,
+    held: f32,
+    held_since: Option<Instant>,
+}
+
+impl PeakHold {
+    #[must_use]
+    pub const fn new(hold_time: Duration) -> Self {
+        Self {
+            hold_time,
+            held: 0.0,
+            held_since: None,
+        }
+    }
+
+    /// Records the current peak, returning the peak that is held.
+    pub fn update(&mut self, peak: f32, now: Instant) -> f32 {
+        let expired = self.held_since.map_or(true, |held_since| {
+            now.duration_since(held_since) >= self.hold_time
+        });
+        if expired || peak >= self.held {
+            self.held = peak;
+            self.held_since = Some(now);
+        }
+        self.held
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::floats::RoughEq;
+
+    #[test]
+    fn set() {
+        let mut levels = Levels::default();
+        levels.set(Meter::LevelLeft, 0.5);
+        levels.set(Meter::PeakRight, 1.5);
+        assert_eq!(
+            levels,
+            Levels {
+                left: Level {
+                    rms: 0.5,
+                    peak: 0.0
+                },
+                right: Level {
+                    rms: 0.0,
+                    peak: 1.0
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn peak_hold() {
+        let mut peak_hold = PeakHold::new(Duration::from_secs(1));
+        let start = Instant::now();
+        assert!(peak_hold.update(0.8, start).roughly_eq(0.8));
+        assert!(peak_hold
+            .update(0.3, start + Duration::from_millis(500))
+            .roughly_eq(0.8));
+        assert!(peak_hold
+            .update(0.9, start + Duration::from_millis(600))
+            .roughly_eq(0.9));
+        assert!(peak_hold
+            .update(0.3, start + Duration::from_millis(1600))
+            .roughly_eq(0.3));
+    }
+}
@@ -0,0 +1,77 @@
+use serde::Deserialize;
+
+/// A named listening level that may be recalled, optionally along with the state of the dim and
+/// mono buttons.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub volume: Option<f32>,
+    pub volume_db: Option<f32>,
+    pub dim: Option<bool>,
+    pub mono: Option<bool>,
+    /// The duration in seconds over which the volume is ramped, which defaults to the ramp
+    /// duration of the volume.
+    pub ramp_time: Option<f64>,
+}
+
+impl Preset {
+    /// The normalised volume of the preset, where a level in decibels takes precedence and is
+    /// converted using the fader law of the mixer.
+    pub fn target_volume(&self, db_to_volume: impl FnOnce(f32) -> f32) -> Option<f32> {
+        self.volume_db.map(db_to_volume).or(self.volume)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fader, floats::RoughEq};
+
+    #[test]
+    fn deserialize() {
+        let preset: Preset = toml::from_str(
+            r#"
+            name = "Late Night"
+            volume_db = -35.0
+            dim = false
+            ramp_time = 2.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            preset,
+            Preset {
+                name: "Late Night".to_string(),
+                volume: None,
+                volume_db: Some(-35.0),
+                dim: Some(false),
+                mono: None,
+                ramp_time: Some(2.0),
+            }
+        );
+    }
+
+    #[test]
+    fn target_volume() {
+        let mut preset = Preset {
+            name: "Reference".to_string(),
+            volume: Some(0.5),
+            volume_db: None,
+            dim: None,
+            mono: None,
+            ramp_time: None,
+        };
+        assert!(preset
+            .target_volume(fader::db_to_volume)
+            .unwrap()
+            .roughly_eq(0.5));
+        preset.volume_db = Some(-18.0);
+        assert!(preset
+            .target_volume(fader::db_to_volume)
+            .unwrap()
+            .roughly_eq(fader::db_to_volume(-18.0)));
+        preset.volume_db = None;
+        preset.volume = None;
+        assert_eq!(preset.target_volume(fader::db_to_volume), None);
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::backend::MixerBackend;
+
+// How sharply the exponential curve eases into the target.
+const EXPONENTIAL_STEEPNESS: f32 = 5.0;
+
+/// The shape of a ramp between two fader positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// Moves the fader at a constant rate.
+    Linear,
+    /// Changes the level by a constant number of decibels per second, which sounds the most even.
+    #[default]
+    LinearDb,
+    /// Moves the fader quickly at first and then eases into the target.
+    Exponential,
+}
+
+impl Curve {
+    fn interpolate(self, from: f32, to: f32, progress: f32, backend: &dyn MixerBackend) -> f32 {
+        match self {
+            Self::Linear => (to - from).mul_add(progress, from),
+            // The bottom of the fader is -oo, so the ramp starts or ends at the lowest usable
+            // level of the mixer instead.
+            Self::LinearDb => {
+                let from_db = backend.volume_to_db(from).max(backend.min_db());
+                let to_db = backend.volume_to_db(to).max(backend.min_db());
+                let db = (to_db - from_db).mul_add(progress, from_db);
+                if to <= 0.0 && progress >= 1.0 {
+                    0.0
+                } else {
+                    backend.db_to_volume(db)
+                }
+            }
+            Self::Exponential => {
+                let eased = (1.0 - (-EXPONENTIAL_STEEPNESS * progress).exp())
+                    / (1.0 - (-EXPONENTIAL_STEEPNESS).exp());
+                (to - from).mul_add(eased, from)
+            }
+        }
+    }
+}
+
+/// A gradual change of the volume from one fader position to another over a period of time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    from: f32,
+    to: f32,
+    duration: Duration,
+    curve: Curve,
+    start: Instant,
+}
+
+impl Ramp {
+    #[must_use]
+    pub const fn new(from: f32, to: f32, duration: Duration, curve: Curve, start: Instant) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            curve,
+            start,
+        }
+    }
+
+    #[must_use]
+    pub const fn target(&self) -> f32 {
+        self.to
+    }
+
+    /// Determines the volume that should have been reached at the given time, following the fader
+    /// law of the mixer.
+    pub fn volume_at(&self, now: Instant, backend: &dyn MixerBackend) -> f32 {
+        if self.is_finished(now) {
+            return self.to;
+        }
+        let progress = now.duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32();
+        self.curve
+            .interpolate(self.from, self.to, progress, backend)
+    }
+
+    #[must_use]
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.duration_since(self.start) >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::TotalMix,
+        fader,
+        testing::assert_close,
+        x32::{Desk, X32},
+    };
+
+    fn volume_at(ramp: &Ramp, now: Instant) -> f32 {
+        ramp.volume_at(now, &TotalMix::default())
+    }
+
+    #[test]
+    fn linear() {
+        let start = Instant::now();
+        let ramp = Ramp::new(0.2, 0.6, Duration::from_secs(4), Curve::Linear, start);
+        assert_close(volume_at(&ramp, start), 0.2);
+        assert_close(volume_at(&ramp, start + Duration::from_secs(1)), 0.3);
+        assert_close(volume_at(&ramp, start + Duration::from_secs(2)), 0.4);
+        assert_close(volume_at(&ramp, start + Duration::from_secs(5)), 0.6);
+        assert!(!ramp.is_finished(start + Duration::from_secs(3)));
+        assert!(ramp.is_finished(start + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn linear_db() {
+        let start = Instant::now();
+        let ramp = Ramp::new(
+            fader::db_to_volume(-40.0),
+            fader::db_to_volume(-20.0),
+            Duration::from_secs(2),
+            Curve::LinearDb,
+            start,
+        );
+        assert_close(
+            fader::volume_to_db(volume_at(&ramp, start + Duration::from_secs(1))),
+            -30.0,
+        );
+    }
+
+    #[test]
+    fn linear_db_to_negative_infinity() {
+        let start = Instant::now();
+        let ramp = Ramp::new(0.5, 0.0, Duration::from_secs(2), Curve::LinearDb, start);
+        // Halfway between -14.58 dB and the lowest usable level.
+        assert!(
+            (fader::volume_to_db(volume_at(&ramp, start + Duration::from_secs(1))) + 39.79).abs()
+                < 0.01
+        );
+        assert_close(volume_at(&ramp, start + Duration::from_secs(2)), 0.0);
+    }
+
+    #[test]
+    fn linear_db_follows_mixer() {
+        let desk = X32::new(Desk::X32);
+        let start = Instant::now();
+        let ramp = Ramp::new(
+            desk.db_to_volume(-30.0),
+            0.0,
+            Duration::from_secs(2),
+            Curve::LinearDb,
+            start,
+        );
+        // Halfway between -30 dB and the lowest level of the desk.
+        assert_close(
+            desk.volume_to_db(ramp.volume_at(start + Duration::from_secs(1), &desk)),
+            -59.75,
+        );
+    }
+
+    #[test]
+    fn exponential() {
+        let start = Instant::now();
+        let ramp = Ramp::new(0.0, 1.0, Duration::from_secs(2), Curve::Exponential, start);
+        assert!(volume_at(&ramp, start + Duration::from_secs(1)) > 0.9);
+        assert_close(volume_at(&ramp, start + Duration::from_secs(2)), 1.0);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, SocketAddrV4, UdpSocket},
+    time::Duration,
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use rosc::{OscBundle, OscMessage, OscPacket, OscType};
+
+use crate::{
+    addresses::{Addresses, Control},
+    buttons::Button,
+    channels::{Bus, ChannelId, Strip},
+    meters::Levels,
+    snapshots::{SnapshotControl, SNAPSHOT_COUNT},
+};
+
+const BANK_SIZE: usize = 8;
+
+/// Readouts shown by the master fader of `TotalMix` FX at known positions of its travel, which the
+/// fader law is checked against.
+///
+/// The simulator only sends a readout while a fader sits at one of these positions, so elsewhere
+/// the manager derives the readout itself, just as it does for devices that don't provide one.
+pub const TOTALMIX_READOUTS: [(f32, &str); 3] = [(0.0, "-oo"), (0.817, "0.0 dB"), (1.0, "6.0 dB")];
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ChannelState {
+    volume: f32,
+    muted: bool,
+    soloed: bool,
+}
+
+#[derive(Debug)]
+struct State {
+    volume: f32,
+    buttons: HashMap<Button, bool>,
+    bus: Bus,
+    bank_start: usize,
+    channels: HashMap<ChannelId, ChannelState>,
+    snapshot_names: Vec<String>,
+    active_snapshot: Option<usize>,
+    send_volume_db: bool,
+}
+
+/// Simulates the OSC interface of `TotalMix` FX so that the sender, receiver and manager may be
+/// exercised end-to-end without an RME device.
+///
+/// Just like `TotalMix`, the simulator listens for incoming messages on one port and sends its
+/// replies to another address.  Requests (negative values) are answered with the current state,
+/// fader writes are applied and echoed back and any press of a control room button toggles it.
+/// Page 1 of the mixer shows banks of 8 channels, which are sent whenever they change, and page 3
+/// holds the snapshots, which are sent whenever one is recalled or renamed.
+#[derive(Debug)]
+pub struct Simulator {
+    socket: UdpSocket,
+    reply_addr: SocketAddrV4,
+    addresses: Addresses,
+    state: Mutex<State>,
+}
+
+// Every error of the simulator is a failure of its socket.
+#[allow(clippy::missing_errors_doc)]
+impl Simulator {
+    pub fn bind(addr: SocketAddrV4, reply_addr: SocketAddrV4) -> Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+        Ok(Self {
+            socket,
+            reply_addr,
+            addresses: Addresses::default(),
+            state: Mutex::new(State {
+                volume: 0.5,
+                buttons: HashMap::new(),
+                bus: Bus::Output,
+                bank_start: 0,
+                channels: HashMap::new(),
+                snapshot_names: (1..=SNAPSHOT_COUNT)
+                    .map(|snapshot| format!("Snapshot {snapshot}"))
+                    .collect(),
+                active_snapshot: None,
+                send_volume_db: true,
+            }),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.state.lock().volume
+    }
+
+    pub fn dimmed(&self) -> bool {
+        self.button_active(Button::Dim)
+    }
+
+    pub fn button_active(&self, button: Button) -> bool {
+        self.state
+            .lock()
+            .buttons
+            .get(&button)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Simulates the volume being changed on the device itself (e.g. via the volume knob).
+    pub fn set_volume(&self, volume: f32) -> Result<()> {
+        self.state.lock().volume = volume.clamp(0.0, 1.0);
+        self.send_state()
+    }
+
+    pub fn channel_volume(&self, channel: ChannelId) -> f32 {
+        self.state
+            .lock()
+            .channels
+            .get(&channel)
+            .map_or(0.0, |channel| channel.volume)
+    }
+
+    pub fn channel_muted(&self, channel: ChannelId) -> bool {
+        self.state
+            .lock()
+            .channels
+            .get(&channel)
+            .map_or(false, |channel| channel.muted)
+    }
+
+    pub fn active_snapshot(&self) -> Option<usize> {
+        self.state.lock().active_snapshot
+    }
+
+    /// Simulates scrolling through the banks of page 1 in `TotalMix` itself, which sends the strips
+    /// of the bank that is now shown but not where the bank starts.
+    pub fn set_bank_start(&self, bank_start: usize) -> Result<()> {
+        self.state.lock().bank_start = bank_start;
+        self.send_bank()
+    }
+
+    /// Simulates a snapshot being recalled in `TotalMix` itself.
+    pub fn recall_snapshot(&self, snapshot: usize) -> Result<()> {
+        self.state.lock().active_snapshot = Some(snapshot);
+        self.send_snapshots()
+    }
+
+    /// Simulates a snapshot being renamed in `TotalMix` itself.
+    pub fn set_snapshot_name(&self, snapshot: usize, name: &str) -> Result<()> {
+        self.state.lock().snapshot_names[snapshot - 1] = name.to_string();
+        self.send_snapshots()
+    }
+
+    /// Simulates the dim button being toggled on the device itself.
+    pub fn set_dimmed(&self, dimmed: bool) -> Result<()> {
+        self.set_button(Button::Dim, dimmed)
+    }
+
+    /// Simulates a control room button being toggled on the device itself.
+    pub fn set_button(&self, button: Button, active: bool) -> Result<()> {
+        self.state.lock().buttons.insert(button, active);
+        self.send_state()
+    }
+
+    /// Simulates the meters of the main output, which are sent separately from the rest of the
+    /// state.
+    pub fn send_levels(&self, levels: &Levels) -> Result<()> {
+        let packet = OscPacket::Bundle(OscBundle {
+            timetag: (0, 1).into(),
+            content: levels
+                .values()
+                .into_iter()
+                .map(|(meter, value)| self.message(Control::Meter(meter), OscType::Float(value)))
+                .collect(),
+        });
+        let packet = rosc::encoder::encode(&packet)?;
+        self.socket.send_to(&packet, self.reply_addr)?;
+        Ok(())
+    }
+
+    /// Sends a packet as is, allowing for the simulation of devices that frame their updates
+    /// differently.
+    pub fn send_packet(&self, packet: &OscPacket) -> Result<()> {
+        let packet = rosc::encoder::encode(packet)?;
+        self.socket.send_to(&packet, self.reply_addr)?;
+        Ok(())
+    }
+
+    /// Sends raw bytes, allowing for the simulation of stray traffic that isn't valid OSC.
+    pub fn send_bytes(&self, bytes: &[u8]) -> Result<()> {
+        self.socket.send_to(bytes, self.reply_addr)?;
+        Ok(())
+    }
+
+    /// Determines whether the decibel readout is sent along with the volume, allowing for the
+    /// simulation of devices that don't provide it.
+    pub fn set_send_volume_db(&self, send_volume_db: bool) {
+        self.state.lock().send_volume_db = send_volume_db;
+    }
+
+    /// Handles the next incoming packet, returning false if nothing arrived before the read
+    /// timeout elapsed.
+    pub fn handle_next(&self) -> Result<bool> {
+        let mut buf = [0u8; rosc::decoder::MTU];
+        let size = match self.socket.recv_from(&mut buf) {
+            Ok((size, _)) => size,
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(false)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let (_, packet) = rosc::decoder::decode_udp(&buf[..size])?;
+        self.handle_packet(packet)?;
+        Ok(true)
+    }
+
+    pub fn run(&self) -> Result<()> {
+        loop {
+            self.handle_next()?;
+        }
+    }
+
+    fn handle_packet(&self, packet: OscPacket) -> Result<()> {
+        match packet {
+            OscPacket::Message(message) => self.handle_message(&message),
+            OscPacket::Bundle(bundle) => {
+                for packet in bundle.content {
+                    self.handle_packet(packet)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn handle_message(&self, message: &OscMessage) -> Result<()> {
+        let value = match message.args.first() {
+            Some(OscType::Float(value)) => *value,
+            _ => return Ok(()),
+        };
+
+        let control = match self.addresses.control(&message.addr) {
+            Some(control) => control,
+            None => return Ok(()),
+        };
+        match control {
+            Control::BankStart => {
+                self.state.lock().bank_start = value.max(0.0) as usize;
+                return self.send_bank();
+            }
+            Control::Bus(bus) => {
+                if value >= 0.5 {
+                    self.state.lock().bus = bus;
+                }
+                return self.send_bank();
+            }
+            Control::Strip(strip) => {
+                let mut state = self.state.lock();
+                let (bus, bank_start) = (state.bus, state.bank_start);
+                let channel_id = |strip| ChannelId {
+                    bus,
+                    number: bank_start + strip,
+                };
+                match strip {
+                    Strip::Volume(strip) if value >= 0.0 => {
+                        state.channels.entry(channel_id(strip)).or_default().volume =
+                            value.min(1.0);
+                    }
+                    // The mute and solo buttons toggle whenever they are pressed.
+                    Strip::Mute(strip) if value >= 0.5 => {
+                        let channel = state.channels.entry(channel_id(strip)).or_default();
+                        channel.muted = !channel.muted;
+                    }
+                    Strip::Solo(strip) if value >= 0.5 => {
+                        let channel = state.channels.entry(channel_id(strip)).or_default();
+                        channel.soloed = !channel.soloed;
+                    }
+                    _ => (),
+                }
+                drop(state);
+                return self.send_bank();
+            }
+            Control::Snapshot(control) => {
+                if let SnapshotControl::Recall(snapshot) = control {
+                    if value >= 0.5 {
+                        self.state.lock().active_snapshot = Some(snapshot);
+                    }
+                }
+                return self.send_snapshots();
+            }
+            Control::Volume => {
+                if value >= 0.0 {
+                    self.state.lock().volume = value.min(1.0);
+                }
+            }
+            // The control room buttons toggle whenever they are pressed.
+            Control::Button(button) => {
+                if value >= 0.5 {
+                    let mut state = self.state.lock();
+                    let active = state.buttons.entry(button).or_default();
+                    *active = !*active;
+                }
+            }
+            Control::VolumeDb | Control::Meter(_) | Control::Mute => return Ok(()),
+        }
+
+        self.send_state()
+    }
+
+    fn send_state(&self) -> Result<()> {
+        let (volume, buttons, send_volume_db) = {
+            let state = self.state.lock();
+            (state.volume, state.buttons.clone(), state.send_volume_db)
+        };
+        let mut content = vec![self.message(Control::Volume, OscType::Float(volume))];
+        if let Some(readout) = readout(volume).filter(|_| send_volume_db) {
+            content.push(self.message(Control::VolumeDb, OscType::String(readout.to_string())));
+        }
+        for button in Button::ALL {
+            let active = buttons.get(&button).copied().unwrap_or(false);
+            content.push(self.message(
+                Control::Button(button),
+                OscType::Float(if active { 1.0 } else { 0.0 }),
+            ));
+        }
+        let packet = OscPacket::Bundle(OscBundle {
+            timetag: (0, 1).into(),
+            content,
+        });
+        let packet = rosc::encoder::encode(&packet)?;
+        self.socket.send_to(&packet, self.reply_addr)?;
+        Ok(())
+    }
+
+    fn send_bank(&self) -> Result<()> {
+        let (bus, bank_start, channels) = {
+            let state = self.state.lock();
+            (state.bus, state.bank_start, state.channels.clone())
+        };
+        let mut content: Vec<_> = Bus::ALL
+            .into_iter()
+            .map(|other| {
+                self.message(
+                    Control::Bus(other),
+                    OscType::Float(if other == bus { 1.0 } else { 0.0 }),
+                )
+            })
+            .collect();
+        for strip in 1..=BANK_SIZE {
+            let number = bank_start + strip;
+            let channel = channels
+                .get(&ChannelId { bus, number })
+                .copied()
+                .unwrap_or_default();
+            content.extend([
+                self.message(
+                    Control::Strip(Strip::Name(strip)),
+                    OscType::String(format!("{bus:?} {number}")),
+                ),
+                self.message(
+                    Control::Strip(Strip::Volume(strip)),
+                    OscType::Float(channel.volume),
+                ),
+            ]);
+            if let Some(readout) = readout(channel.volume) {
+                content.push(self.message(
+                    Control::Strip(Strip::VolumeDb(strip)),
+                    OscType::String(readout.to_string()),
+                ));
+            }
+            content.extend([
+                self.message(
+                    Control::Strip(Strip::Mute(strip)),
+                    OscType::Float(if channel.muted { 1.0 } else { 0.0 }),
+                ),
+                self.message(
+                    Control::Strip(Strip::Solo(strip)),
+                    OscType::Float(if channel.soloed { 1.0 } else { 0.0 }),
+                ),
+            ]);
+        }
+        let packet = OscPacket::Bundle(OscBundle {
+            timetag: (0, 1).into(),
+            content,
+        });
+        let packet = rosc::encoder::encode(&packet)?;
+        self.socket.send_to(&packet, self.reply_addr)?;
+        Ok(())
+    }
+
+    fn message(&self, control: Control, arg: OscType) -> OscPacket {
+        OscPacket::Message(OscMessage {
+            addr: self.addresses.osc_addr(control).unwrap_or_default(),
+            args: vec![arg],
+        })
+    }
+
+    fn send_snapshots(&self) -> Result<()> {
+        let (names, active) = {
+            let state = self.state.lock();
+            (state.snapshot_names.clone(), state.active_snapshot)
+        };
+        let mut content = Vec::new();
+        for (snapshot, name) in (1..=SNAPSHOT_COUNT).zip(names) {
+            content.extend([
+                self.message(
+                    Control::Snapshot(SnapshotControl::Name(snapshot)),
+                    OscType::String(name),
+                ),
+                self.message(
+                    Control::Snapshot(SnapshotControl::Recall(snapshot)),
+                    OscType::Float(if active == Some(snapshot) { 1.0 } else { 0.0 }),
+                ),
+            ]);
+        }
+        let packet = OscPacket::Bundle(OscBundle {
+            timetag: (0, 1).into(),
+            content,
+        });
+        let packet = rosc::encoder::encode(&packet)?;
+        self.socket.send_to(&packet, self.reply_addr)?;
+        Ok(())
+    }
+}
+
+fn readout(volume: f32) -> Option<&'static str> {
+    TOTALMIX_READOUTS
+        .iter()
+        .find(|(position, _)| (position - volume).abs() < 0.0005)
+        .map(|(_, readout)| *readout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        floats::RoughEq,
+        testing::{bind, connect},
+    };
+
+    #[test]
+    fn answers_requests() {
+        let (simulator, manager) = bind();
+        manager.request_volume().unwrap();
+        for _ in 0..=Button::ALL.len() {
+            assert!(simulator.handle_next().unwrap());
+        }
+        while manager.recieve_volume().unwrap() {}
+        assert!(manager.volume().roughly_eq(0.5));
+        assert_eq!(manager.volume_db().unwrap(), "-14.6 dB");
+        assert!(!simulator.handle_next().unwrap());
+    }
+
+    #[test]
+    fn sends_known_readouts() {
+        let (simulator, manager) = connect();
+        for (position, readout) in TOTALMIX_READOUTS {
+            simulator.set_volume(position).unwrap();
+            assert!(manager.recieve_volume().unwrap());
+            assert_eq!(manager.volume_db().unwrap(), readout);
+        }
+    }
+}
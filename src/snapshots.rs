@@ -0,0 +1,144 @@
+use anyhow::Result;
+use rosc::OscType;
+use thiserror::Error;
+
+/// The number of mixer snapshots that `TotalMix` stores.
+pub const SNAPSHOT_COUNT: usize = 8;
+
+#[derive(Error, Debug)]
+#[error("snapshot must be no less than 1 and no more than {SNAPSHOT_COUNT}")]
+struct SnapshotRangeError;
+
+/// Checks that the snapshot is numbered from 1 to [`SNAPSHOT_COUNT`].
+///
+/// # Errors
+///
+/// Returns an error if the snapshot is out of range.
+pub fn check_snapshot(snapshot: usize) -> Result<()> {
+    if !(1..=SNAPSHOT_COUNT).contains(&snapshot) {
+        return Err(SnapshotRangeError.into());
+    }
+    Ok(())
+}
+
+/// The snapshot controls on page 3, where the snapshots are numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotControl {
+    /// Recalls the snapshot when pressed and reports whether it is the active snapshot.
+    Recall(usize),
+    Name(usize),
+}
+
+/// A change in the snapshots reported by the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChange {
+    Recalled(usize),
+    Named(usize, String),
+}
+
+/// The names of the snapshots along with the active snapshot, which are unknown until the device
+/// reports them.
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    names: [Option<String>; SNAPSHOT_COUNT],
+    active: Option<usize>,
+    requested: Option<usize>,
+}
+
+impl Snapshots {
+    #[must_use]
+    pub fn name(&self, snapshot: usize) -> Option<&str> {
+        self.names.get(snapshot.checked_sub(1)?)?.as_deref()
+    }
+
+    #[must_use]
+    pub const fn active(&self) -> Option<usize> {
+        self.active
+    }
+
+    /// Records that the snapshot has been recalled so that the device reporting it can be told
+    /// apart from a recall made in `TotalMix`.
+    pub const fn request(&mut self, snapshot: usize) {
+        self.requested = Some(snapshot);
+    }
+
+    /// Forgets the request for the snapshot once the device reports it, returning whether it was
+    /// requested.
+    pub fn take_requested(&mut self, snapshot: usize) -> bool {
+        let requested = self.requested == Some(snapshot);
+        if requested {
+            self.requested = None;
+        }
+        requested
+    }
+
+    /// Records a message received for a snapshot control, returning what changed.
+    pub fn receive(&mut self, control: SnapshotControl, arg: &OscType) -> Option<SnapshotChange> {
+        match (control, arg) {
+            (SnapshotControl::Recall(snapshot), OscType::Float(value)) => {
+                let active = *value >= 0.5;
+                if active && self.active != Some(snapshot) {
+                    self.active = Some(snapshot);
+                    return Some(SnapshotChange::Recalled(snapshot));
+                }
+                if !active && self.active == Some(snapshot) {
+                    self.active = None;
+                }
+                None
+            }
+            (SnapshotControl::Name(snapshot), OscType::String(name)) => {
+                let current = &mut self.names[snapshot - 1];
+                if current.as_deref() == Some(name.as_str()) {
+                    return None;
+                }
+                *current = Some(name.clone());
+                Some(SnapshotChange::Named(snapshot, name.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receive() {
+        let mut snapshots = Snapshots::default();
+        assert_eq!(
+            snapshots.receive(
+                SnapshotControl::Name(2),
+                &OscType::String("Mixing".to_string())
+            ),
+            Some(SnapshotChange::Named(2, "Mixing".to_string()))
+        );
+        assert_eq!(snapshots.name(2), Some("Mixing"));
+        assert_eq!(snapshots.name(0), None);
+
+        assert_eq!(
+            snapshots.receive(SnapshotControl::Recall(2), &OscType::Float(1.0)),
+            Some(SnapshotChange::Recalled(2))
+        );
+        assert_eq!(
+            snapshots.receive(SnapshotControl::Recall(2), &OscType::Float(1.0)),
+            None
+        );
+        assert_eq!(
+            snapshots.receive(SnapshotControl::Recall(1), &OscType::Float(0.0)),
+            None
+        );
+        assert_eq!(snapshots.active(), Some(2));
+        snapshots.receive(SnapshotControl::Recall(2), &OscType::Float(0.0));
+        assert_eq!(snapshots.active(), None);
+    }
+
+    #[test]
+    fn take_requested() {
+        let mut snapshots = Snapshots::default();
+        snapshots.request(3);
+        assert!(!snapshots.take_requested(1));
+        assert!(snapshots.take_requested(3));
+        assert!(!snapshots.take_requested(3));
+    }
+}
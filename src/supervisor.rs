@@ -0,0 +1,258 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{
+    comms::{Receiver, Sender},
+    manager::Manager,
+};
+
+/// How long the device is given to answer a probe before it is shown as not responding.
+const REPLY_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+#[error("device timeout must be greater than 0")]
+struct DeviceTimeoutRangeError;
+
+#[derive(Error, Debug)]
+#[error("reconnect interval must be greater than 0 and no more than the max reconnect interval")]
+struct ReconnectIntervalRangeError;
+
+/// Keeps the manager in sync with the device, requesting the current state until the device
+/// responds, probing it when it goes quiet and resetting the manager when it disappears.
+#[derive(Debug)]
+pub struct Supervisor {
+    device_timeout: Duration,
+    reconnect_interval: Duration,
+    max_reconnect_interval: Duration,
+    backoff: Duration,
+    last_received: Option<Instant>,
+    last_request: Option<Instant>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self {
+            device_timeout: Duration::from_secs(5),
+            reconnect_interval: Duration::from_secs(1),
+            max_reconnect_interval: Duration::from_secs(30),
+            backoff: Duration::from_secs(1),
+            last_received: None,
+            last_request: None,
+        }
+    }
+}
+
+impl Supervisor {
+    /// Sets how long the device may go without sending anything before it's considered stale.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timeout is 0.
+    pub fn set_device_timeout(&mut self, device_timeout: Duration) -> Result<()> {
+        if device_timeout.is_zero() {
+            return Err(DeviceTimeoutRangeError.into());
+        }
+        self.device_timeout = device_timeout;
+        Ok(())
+    }
+
+    /// Sets how often the state is requested while disconnected, which backs off up to the maximum
+    /// interval.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the interval is 0 or longer than the maximum interval.
+    pub fn set_reconnect_interval(
+        &mut self,
+        reconnect_interval: Duration,
+        max_reconnect_interval: Duration,
+    ) -> Result<()> {
+        if reconnect_interval.is_zero() || reconnect_interval > max_reconnect_interval {
+            return Err(ReconnectIntervalRangeError.into());
+        }
+        self.reconnect_interval = reconnect_interval;
+        self.max_reconnect_interval = max_reconnect_interval;
+        self.backoff = reconnect_interval;
+        Ok(())
+    }
+
+    pub fn run<S: Sender, R: Receiver>(&mut self, manager: &Manager<S, R>) {
+        loop {
+            if let Err(error) = self.tick(manager) {
+                eprintln!("unable to communicate with the device: {error}");
+                thread::sleep(self.backoff);
+                self.increase_backoff();
+            }
+        }
+    }
+
+    /// Receives from the device and then requests, reminds or marks the connection stale as is due.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device couldn't be communicated with.
+    pub fn tick<S: Sender, R: Receiver>(&mut self, manager: &Manager<S, R>) -> Result<()> {
+        let received = manager.recieve_volume()?;
+        self.tick_at(manager, received, Instant::now())
+    }
+
+    fn tick_at<S: Sender, R: Receiver>(
+        &mut self,
+        manager: &Manager<S, R>,
+        received: bool,
+        now: Instant,
+    ) -> Result<()> {
+        manager.verify_buttons()?;
+        manager.keep_alive()?;
+        if received {
+            self.last_received = Some(now);
+            self.backoff = self.reconnect_interval;
+        }
+
+        if manager.initialized() {
+            let last_received = *self.last_received.get_or_insert(now);
+            if now.duration_since(last_received) < self.device_timeout {
+                return Ok(());
+            }
+
+            match self.last_request {
+                // The device was probed after we last heard from it and hasn't replied in time,
+                // so it has gone away and the current state can no longer be trusted.
+                Some(last_request) if last_request > last_received => {
+                    let unanswered = now.duration_since(last_request);
+                    if unanswered >= self.device_timeout {
+                        manager.reset();
+                        self.last_request = None;
+                    } else if unanswered >= REPLY_WINDOW.min(self.device_timeout / 2) {
+                        manager.mark_stale();
+                    }
+                }
+                _ => self.request(manager, now)?,
+            }
+        } else if self.last_request.map_or(true, |last_request| {
+            now.duration_since(last_request) >= self.backoff
+        }) {
+            if self.last_request.is_some() {
+                self.increase_backoff();
+            }
+            self.request(manager, now)?;
+        }
+
+        Ok(())
+    }
+
+    fn request<S: Sender, R: Receiver>(
+        &mut self,
+        manager: &Manager<S, R>,
+        now: Instant,
+    ) -> Result<()> {
+        self.last_request = Some(now);
+        manager.request_volume()
+    }
+
+    fn increase_backoff(&mut self) {
+        self.backoff = (self.backoff * 2).min(self.max_reconnect_interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comms::{UdpReceiver, UdpSender},
+        manager::ConnectionState,
+        sim::Simulator,
+        testing::{localhost, to_v4},
+    };
+
+    fn setup() -> (Simulator, Manager<UdpSender, UdpReceiver>, Supervisor) {
+        let receiver = UdpReceiver::bind(localhost()).unwrap();
+        receiver
+            .set_read_timeout(Duration::from_millis(20))
+            .unwrap();
+        let simulator =
+            Simulator::bind(localhost(), to_v4(receiver.local_addr().unwrap())).unwrap();
+        let sender = UdpSender::new(to_v4(simulator.local_addr().unwrap())).unwrap();
+
+        let mut manager = Manager::default();
+        manager.set_sender(sender);
+        manager.set_receiver(receiver);
+
+        let mut supervisor = Supervisor::default();
+        supervisor
+            .set_device_timeout(Duration::from_millis(50))
+            .unwrap();
+        supervisor
+            .set_reconnect_interval(Duration::from_millis(10), Duration::from_millis(40))
+            .unwrap();
+
+        (simulator, manager, supervisor)
+    }
+
+    #[test]
+    fn connects_to_device() {
+        let (simulator, manager, mut supervisor) = setup();
+        supervisor.tick(&manager).unwrap();
+        assert!(simulator.handle_next().unwrap());
+        assert!(simulator.handle_next().unwrap());
+        supervisor.tick(&manager).unwrap();
+        assert!(manager.initialized());
+        assert_eq!(manager.connection_state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn backs_off_while_device_is_unavailable() {
+        let (_simulator, manager, mut supervisor) = setup();
+        supervisor.tick(&manager).unwrap();
+        for _ in 0..10 {
+            supervisor.tick(&manager).unwrap();
+        }
+        assert!(!manager.initialized());
+        assert_eq!(manager.connection_state(), ConnectionState::Requesting);
+        assert_eq!(supervisor.backoff, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn resets_and_recovers_when_device_disappears() {
+        let (simulator, manager, mut supervisor) = setup();
+        supervisor.tick(&manager).unwrap();
+        simulator.handle_next().unwrap();
+        simulator.handle_next().unwrap();
+        supervisor.tick(&manager).unwrap();
+        assert!(manager.initialized());
+
+        // The device goes quiet, which is expected until it is probed and doesn't answer.
+        let quiet = Instant::now();
+        supervisor.tick_at(&manager, false, quiet).unwrap();
+        assert_eq!(manager.connection_state(), ConnectionState::Connected);
+        let probed = quiet + Duration::from_millis(60);
+        supervisor.tick_at(&manager, false, probed).unwrap();
+        assert_eq!(manager.connection_state(), ConnectionState::Connected);
+        supervisor
+            .tick_at(&manager, false, probed + Duration::from_millis(30))
+            .unwrap();
+        assert!(matches!(
+            manager.connection_state(),
+            ConnectionState::Stale { .. }
+        ));
+
+        // Once the probe has gone unanswered for the device timeout, the device is considered gone.
+        supervisor
+            .tick_at(&manager, false, probed + Duration::from_millis(50))
+            .unwrap();
+        assert!(!manager.initialized());
+        assert_ne!(manager.connection_state(), ConnectionState::Connected);
+
+        // The device comes back and answers the next request.
+        while simulator.handle_next().unwrap() {}
+        for _ in 0..5 {
+            supervisor.tick(&manager).unwrap();
+        }
+        assert!(manager.initialized());
+    }
+}
@@ -0,0 +1,82 @@
+//! Helpers shared by the tests of several modules.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+
+use rosc::{OscMessage, OscPacket};
+
+use crate::{
+    buttons::Button,
+    comms::{UdpReceiver, UdpSender},
+    manager::Manager,
+    sim::Simulator,
+};
+
+pub fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "{actual} is not close to {expected}"
+    );
+}
+
+/// An address on the loopback interface with a port chosen by the OS.
+pub const fn localhost() -> SocketAddrV4 {
+    SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)
+}
+
+pub fn to_v4(addr: SocketAddr) -> SocketAddrV4 {
+    match addr.ip() {
+        IpAddr::V4(ip) => SocketAddrV4::new(ip, addr.port()),
+        IpAddr::V6(_) => unreachable!(),
+    }
+}
+
+/// Binds a simulator and a manager to each other without requesting anything.
+pub fn bind() -> (Simulator, Manager<UdpSender, UdpReceiver>) {
+    let receiver = UdpReceiver::bind(localhost()).unwrap();
+    let simulator = Simulator::bind(localhost(), to_v4(receiver.local_addr().unwrap())).unwrap();
+    let sender = UdpSender::new(to_v4(simulator.local_addr().unwrap())).unwrap();
+
+    let mut manager = Manager::default();
+    manager.set_sender(sender);
+    manager.set_receiver(receiver);
+    (simulator, manager)
+}
+
+/// Binds a simulator and a manager to each other and initializes the manager with the state of
+/// the simulator.
+pub fn connect() -> (Simulator, Manager<UdpSender, UdpReceiver>) {
+    let (simulator, manager) = bind();
+
+    // The volume and each of the buttons are requested separately.
+    manager.request_volume().unwrap();
+    for _ in 0..=Button::ALL.len() {
+        assert!(simulator.handle_next().unwrap());
+    }
+    for _ in 0..=Button::ALL.len() {
+        assert!(manager.recieve_volume().unwrap());
+    }
+
+    (simulator, manager)
+}
+
+/// Binds a socket that stands in for a mixer other than `TotalMix`, returning it with its address.
+pub fn bind_desk() -> (UdpSocket, SocketAddrV4) {
+    let desk = UdpSocket::bind(localhost()).unwrap();
+    let addr = to_v4(desk.local_addr().unwrap());
+    (desk, addr)
+}
+
+/// Receives the next message sent to the desk along with the address it was sent from.
+pub fn receive(desk: &UdpSocket) -> (OscMessage, SocketAddr) {
+    let mut buf = [0u8; rosc::decoder::MTU];
+    let (size, addr) = desk.recv_from(&mut buf).unwrap();
+    match rosc::decoder::decode_udp(&buf[..size]).unwrap().1 {
+        OscPacket::Message(message) => (message, addr),
+        OscPacket::Bundle(_) => unreachable!(),
+    }
+}
+
+pub fn reply(desk: &UdpSocket, addr: SocketAddr, message: OscMessage) {
+    let packet = rosc::encoder::encode(&OscPacket::Message(message)).unwrap();
+    desk.send_to(&packet, addr).unwrap();
+}
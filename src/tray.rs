@@ -3,24 +3,70 @@ use system_tray::{
     icon::Icon,
     menu::{
         menu_event_receiver, AboutMetadata, Menu, MenuEventReceiver, MenuItem, PredefinedMenuItem,
+        Submenu,
     },
     tray_event_receiver, TrayEventReceiver, TrayIcon, TrayIconBuilder,
 };
 
+use crate::{actions::Action, config, presets::Preset, snapshots::SNAPSHOT_COUNT};
+
 pub enum MenuAction {
+    Perform(Action),
     Exit,
 }
 
 pub struct Tray<'a> {
     tray_icon: Option<TrayIcon>,
+    action_menu_items: Vec<(MenuItem, Action)>,
     exit_menu_item: MenuItem,
     menu_event_receiver: &'a MenuEventReceiver,
     tray_event_receiver: &'a TrayEventReceiver,
 }
 
 impl<'a> Tray<'a> {
-    pub fn new() -> Result<Self> {
+    /// Creates the tray icon and its menu, where the snapshots are only enabled for mixers that
+    /// have them.
+    pub fn new(config: &config::Tray, presets: &[Preset], snapshots: bool) -> Result<Self> {
         let tray_menu = Menu::new();
+        let mut action_menu_items = Vec::new();
+
+        let volume_menu = Submenu::new("Set Volume", true);
+        for &db in &config.volume_levels_db {
+            let menu_item = MenuItem::new(format!("{db} dB"), true, None);
+            volume_menu.append(&menu_item);
+            action_menu_items.push((menu_item, Action::SetVolumeDb { db }));
+        }
+
+        let presets_menu = Submenu::new("Presets", !presets.is_empty());
+        for preset in presets {
+            let menu_item = MenuItem::new(&preset.name, true, None);
+            presets_menu.append(&menu_item);
+            action_menu_items.push((
+                menu_item,
+                Action::RecallPreset {
+                    preset: preset.name.clone(),
+                },
+            ));
+        }
+
+        let snapshots_menu = Submenu::new("Snapshots", snapshots);
+        for snapshot in 1..=SNAPSHOT_COUNT {
+            let menu_item = MenuItem::new(format!("Snapshot {snapshot}"), true, None);
+            snapshots_menu.append(&menu_item);
+            action_menu_items.push((menu_item, Action::RecallSnapshot { snapshot }));
+        }
+
+        let sleep_timer_menu = Submenu::new("Sleep Timer", true);
+        for &minutes in &config.sleep_timer_minutes {
+            let menu_item = MenuItem::new(format!("Fade Out Over {minutes} Minutes"), true, None);
+            sleep_timer_menu.append(&menu_item);
+            action_menu_items.push((menu_item, Action::FadeOut { minutes }));
+        }
+        let cancel_menu_item = MenuItem::new("Cancel", true, None);
+        sleep_timer_menu.append(&PredefinedMenuItem::separator());
+        sleep_timer_menu.append(&cancel_menu_item);
+        action_menu_items.push((cancel_menu_item, Action::CancelFade));
+
         let exit_menu_item = MenuItem::new("Exit", true, None);
         tray_menu.append_items(&[
             &PredefinedMenuItem::about(
@@ -35,6 +81,11 @@ impl<'a> Tray<'a> {
                 }),
             ),
             &PredefinedMenuItem::separator(),
+            &volume_menu,
+            &presets_menu,
+            &snapshots_menu,
+            &sleep_timer_menu,
+            &PredefinedMenuItem::separator(),
             &exit_menu_item,
         ]);
 
@@ -46,6 +97,7 @@ impl<'a> Tray<'a> {
 
         Ok(Self {
             tray_icon: Some(tray_icon),
+            action_menu_items,
             exit_menu_item,
             menu_event_receiver: menu_event_receiver(),
             tray_event_receiver: tray_event_receiver(),
@@ -59,15 +111,26 @@ impl<'a> Tray<'a> {
         Ok(())
     }
 
+    /// Labels the menu item of the snapshot with the name that the device reported for it.
+    pub fn set_snapshot_name(&self, snapshot: usize, name: &str) {
+        for (menu_item, action) in &self.action_menu_items {
+            if *action == (Action::RecallSnapshot { snapshot }) {
+                menu_item.set_text(format!("{snapshot}: {name}"));
+            }
+        }
+    }
+
     pub fn receive_menu_event(&self) -> Option<MenuAction> {
         self.menu_event_receiver
             .try_recv()
             .map_or(None, |menu_event| {
                 if menu_event.id == self.exit_menu_item.id() {
-                    Some(MenuAction::Exit)
-                } else {
-                    None
+                    return Some(MenuAction::Exit);
                 }
+                self.action_menu_items
+                    .iter()
+                    .find(|(menu_item, _)| menu_event.id == menu_item.id())
+                    .map(|(_, action)| MenuAction::Perform(action.clone()))
             })
     }
 
@@ -0,0 +1,255 @@
+use std::time::Duration;
+
+use rosc::{OscMessage, OscType};
+use serde::Deserialize;
+
+use crate::{
+    addresses::Control,
+    backend::{number, MixerBackend},
+};
+
+// The desk only sends updates to clients that have subscribed within the last 10 seconds.
+const XREMOTE_OSC_ADDR: &str = "/xremote";
+const XREMOTE_INTERVAL: Duration = Duration::from_secs(8);
+
+// The level of the lowest of the 1024 steps of the fader above -oo.
+const MIN_DB: f32 = -89.5;
+
+/// The Behringer desks that share the X32 OSC protocol, which differ in where the main stereo bus
+/// lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Desk {
+    X32,
+    XAir,
+}
+
+impl Desk {
+    const fn fader_osc_addr(self) -> &'static str {
+        match self {
+            Self::X32 => "/main/st/mix/fader",
+            Self::XAir => "/lr/mix/fader",
+        }
+    }
+
+    const fn on_osc_addr(self) -> &'static str {
+        match self {
+            Self::X32 => "/main/st/mix/on",
+            Self::XAir => "/lr/mix/on",
+        }
+    }
+}
+
+/// A Behringer X32 or X Air (e.g. XR18) desk, where the main fader provides the volume and the
+/// main on button provides the mute.
+#[derive(Debug)]
+pub struct X32 {
+    desk: Desk,
+}
+
+impl X32 {
+    #[must_use]
+    pub const fn new(desk: Desk) -> Self {
+        Self { desk }
+    }
+}
+
+impl MixerBackend for X32 {
+    // The desk replies to an address without arguments with the current value.
+    fn request(&self) -> Vec<OscMessage> {
+        let mut messages = self.keep_alive();
+        messages.extend(
+            [self.desk.fader_osc_addr(), self.desk.on_osc_addr()].map(|addr| message(addr, None)),
+        );
+        messages
+    }
+
+    fn decode(&self, message: &OscMessage) -> Option<(Control, OscType)> {
+        let addr = message.addr.as_str();
+        let value = number(message.args.first()?)?;
+        if addr == self.desk.fader_osc_addr() {
+            Some((Control::Volume, OscType::Float(value)))
+        } else if addr == self.desk.on_osc_addr() {
+            Some((
+                Control::Mute,
+                OscType::Float(if value < 0.5 { 1.0 } else { 0.0 }),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self, control: Control, value: f32) -> Option<OscMessage> {
+        match control {
+            Control::Volume => Some(message(
+                self.desk.fader_osc_addr(),
+                Some(OscType::Float(value.clamp(0.0, 1.0))),
+            )),
+            // The desk turns the bus on and off rather than muting it.
+            Control::Mute => Some(message(
+                self.desk.on_osc_addr(),
+                Some(OscType::Int(i32::from(value < 0.5))),
+            )),
+            _ => None,
+        }
+    }
+
+    fn keep_alive_interval(&self) -> Option<Duration> {
+        Some(XREMOTE_INTERVAL)
+    }
+
+    fn keep_alive(&self) -> Vec<OscMessage> {
+        vec![message(XREMOTE_OSC_ADDR, None)]
+    }
+
+    fn replies_to_sender(&self) -> bool {
+        true
+    }
+
+    fn volume_to_db(&self, volume: f32) -> f32 {
+        volume_to_db(volume)
+    }
+
+    fn db_to_volume(&self, db: f32) -> f32 {
+        db_to_volume(db)
+    }
+
+    fn min_db(&self) -> f32 {
+        MIN_DB
+    }
+}
+
+fn message(addr: &str, arg: Option<OscType>) -> OscMessage {
+    OscMessage {
+        addr: addr.to_string(),
+        args: arg.into_iter().collect(),
+    }
+}
+
+// The fader law of the X32, which is made up of four linear segments with 0 dB at 75% of the
+// fader's travel, 10 dB of gain at the top and -oo at the bottom.
+fn volume_to_db(volume: f32) -> f32 {
+    let volume = volume.min(1.0);
+    if volume <= 0.0 {
+        f32::NEG_INFINITY
+    } else if volume < 0.0625 {
+        volume.mul_add(480.0, -90.0)
+    } else if volume < 0.25 {
+        volume.mul_add(160.0, -70.0)
+    } else if volume < 0.5 {
+        volume.mul_add(80.0, -50.0)
+    } else {
+        volume.mul_add(40.0, -30.0)
+    }
+}
+
+fn db_to_volume(db: f32) -> f32 {
+    let volume = if db == f32::NEG_INFINITY {
+        0.0
+    } else if db < -60.0 {
+        (db + 90.0) / 480.0
+    } else if db < -30.0 {
+        (db + 70.0) / 160.0
+    } else if db < -10.0 {
+        (db + 50.0) / 80.0
+    } else {
+        (db + 30.0) / 40.0
+    };
+    volume.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comms::{UdpReceiver, UdpSender},
+        manager::Manager,
+        testing::{assert_close, bind_desk, localhost, receive, reply},
+    };
+
+    #[test]
+    fn fader_law() {
+        assert_close(volume_to_db(0.75), 0.0);
+        assert_close(volume_to_db(1.0), 10.0);
+        assert!(volume_to_db(0.0).is_infinite());
+        for db in [-80.0, -45.0, -20.0, -6.0, 0.0, 10.0] {
+            assert_close(volume_to_db(db_to_volume(db)), db);
+        }
+        assert_close(db_to_volume(20.0), 1.0);
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let backend = X32::new(Desk::XAir);
+        assert_eq!(
+            backend.decode(&message("/lr/mix/on", Some(OscType::Int(0)))),
+            Some((Control::Mute, OscType::Float(1.0)))
+        );
+        assert_eq!(
+            backend.decode(&message("/lr/mix/fader", Some(OscType::Float(0.75)))),
+            Some((Control::Volume, OscType::Float(0.75)))
+        );
+        assert_eq!(
+            backend.decode(&message("/main/st/mix/fader", Some(OscType::Float(0.75)))),
+            None
+        );
+        assert_eq!(
+            backend.encode(Control::Mute, 0.0),
+            Some(message("/lr/mix/on", Some(OscType::Int(1))))
+        );
+        assert!(!backend.supports(Control::VolumeDb));
+        assert_eq!(backend.request()[0].addr, "/xremote");
+    }
+
+    #[test]
+    fn manager() {
+        let (desk, desk_addr) = bind_desk();
+        let receiver = UdpReceiver::bind(localhost()).unwrap();
+        let mut manager: Manager<UdpSender, UdpReceiver> = Manager::default();
+        manager.set_backend(Box::new(X32::new(Desk::X32)));
+        manager.set_sender(receiver.sender(desk_addr).unwrap());
+        manager.set_receiver(receiver);
+
+        manager.request_volume().unwrap();
+        let mut reply_addr = None;
+        for _ in 0..3 {
+            let (message, addr) = receive(&desk);
+            assert!(message.args.is_empty());
+            reply_addr = Some(addr);
+        }
+        let reply_addr = reply_addr.unwrap();
+        reply(
+            &desk,
+            reply_addr,
+            message("/main/st/mix/fader", Some(OscType::Float(0.75))),
+        );
+        assert!(manager.recieve_volume().unwrap());
+
+        // The mute of the desk must be known before it's safe to toggle.
+        assert!(!manager.initialized());
+        reply(
+            &desk,
+            reply_addr,
+            message("/main/st/mix/on", Some(OscType::Int(1))),
+        );
+        assert!(manager.recieve_volume().unwrap());
+        assert!(manager.initialized());
+        assert_eq!(manager.volume_db().unwrap(), "0.0 dB");
+        assert!(!manager.muted());
+
+        assert!(manager.toggle_mute().unwrap());
+        assert!(manager.muted());
+        assert_eq!(
+            receive(&desk).0,
+            message("/main/st/mix/on", Some(OscType::Int(0)))
+        );
+        assert!(!manager.toggle_dim().unwrap());
+
+        // Steps in decibels reach down to the lowest level of the desk rather than that of
+        // `TotalMix`.
+        manager.set_increment_db(6.0).unwrap();
+        assert!(manager.set_volume_db(-64.0).unwrap());
+        assert!(manager.decrease_volume().unwrap());
+        assert_eq!(manager.volume_db().unwrap(), "-70.0 dB");
+    }
+}